@@ -1,21 +1,1708 @@
-use crate::board::{Manager, Move, Piece};
-use rand::{thread_rng, Rng};
+use crate::board::{Manager, Move, Piece, Player, Variant};
+use crate::book::{Book, BookSelection};
+use crate::egdb::{EndgameDb, Outcome};
+use crate::nn::Network;
+use crate::pdn;
+use crate::tt::{self, Bound, Entry, TranspositionTable};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{fs, io};
 
-fn generate_all_moves(manager: &Manager) -> Vec<Move> {
-    let mut generated_moves = Vec::new();
+mod difficulty;
+mod ponder;
 
-    for (_, index) in manager.get_pieces(manager.current_side()) {
-        generated_moves.extend(manager.piece_moves(index).iter().map(|x| x.to_owned()));
+pub use difficulty::{Difficulty, Weakening};
+pub use ponder::{BackgroundSearch, Ponder};
+
+const SEARCH_DEPTH: u8 = 6;
+const HASH_ENTRIES: usize = 1 << 18;
+
+// how many times quiescence answers a capture threat with a quiet move, and a limit on
+// how far it goes at all
+const THREAT_PLIES: u8 = 1;
+const MAX_PLY: i32 = 100;
+
+// aspiration windows start this far either side of the score of the iteration before
+const ASPIRATION_WINDOW: i32 = 50;
+
+// late move reductions, quiet moves from this far down the ordering are searched a ply
+// shallower first when there is at least this much depth left
+const LMR_MOVES: usize = 3;
+const LMR_DEPTH: u8 = 3;
+
+// deeper iterations are left out of the search tree dump, it grows too fast to be readable
+pub const TREE_DUMP_DEPTH: u8 = 4;
+
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    pub depth: u8,
+    pub egdb: Option<Arc<EndgameDb>>, // looked up once few enough pieces are left
+    pub book: Option<Arc<Book>>,      // played from before searching
+    pub book_selection: BookSelection,
+    pub network: Option<Arc<Network>>, // replaces `evaluate` in standard checkers
+    pub weights: Weights,
+    pub quiescence: bool, // keep searching captures past the depth
+    pub aspiration: bool, // each iteration is tried in a narrow window around the last score
+    pub pvs: bool,        // moves after the first only have to be shown not to be better
+    pub lmr: bool,        // late quiet moves are searched shallower unless they look good
+    pub hash_entries: usize,
+    pub threads: usize, // helper threads share the hash table, 1 searches on its own
+    pub multi_pv: usize, // how many of the best moves get an exact score and a line
+    pub time: Option<Duration>, // stops deepening once it runs out, the first ply is always searched
+    pub weakening: Option<Weakening>,
+    pub events: Option<Sender<SearchEvent>>, // told about every iteration and the end
+    pub tree_dump: Option<String>,           // every node searched is written to this file
+    pub seed: Option<u64>, // for tie-breaks, the book and weakening, without one they vary
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            depth: SEARCH_DEPTH,
+            egdb: None,
+            book: None,
+            book_selection: BookSelection::default(),
+            network: None,
+            weights: Weights::default(),
+            quiescence: true,
+            aspiration: true,
+            pvs: true,
+            lmr: true,
+            hash_entries: HASH_ENTRIES,
+            threads: 1,
+            multi_pv: 1,
+            time: None,
+            weakening: None,
+            events: None,
+            tree_dump: None,
+            seed: None,
+        }
+    }
+}
+
+pub const WIN_SCORE: i32 = 100_000;
+
+// the evaluation is a weighted sum of these terms, counted for each side
+pub const TERMS: usize = 7;
+pub const TERM_NAMES: [&str; TERMS] = [
+    "man",
+    "king",
+    "back_rank",
+    "centre",
+    "tempo",
+    "runaway",
+    "mobility",
+];
+
+const MAN: usize = 0;
+const KING: usize = 1;
+const BACK_RANK: usize = 2; // men still guarding the row the other side kings on
+const CENTRE: usize = 3;
+const TEMPO: usize = 4; // rows the men have moved up
+const RUNAWAY: usize = 5; // men nothing can stop from kinging
+const MOBILITY: usize = 6; // empty squares the pieces could step to
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub struct Weights(pub [i32; TERMS]);
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights([100, 160, 8, 6, 2, 30, 2])
+    }
+}
+
+impl Weights {
+    // one `name value` pair per line
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text: String = TERM_NAMES
+            .iter()
+            .zip(self.0)
+            .map(|(name, value)| format!("{} {}\n", name, value))
+            .collect();
+
+        fs::write(path, text)
+    }
+
+    // terms missing from the file keep their default weight
+    pub fn load(path: &str) -> io::Result<Self> {
+        let invalid = |line: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid weight: {}", line),
+            )
+        };
+        let mut weights = Weights::default();
+
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, value) = line.split_once(' ').ok_or_else(|| invalid(line))?;
+            let term = TERM_NAMES
+                .iter()
+                .position(|x| *x == name)
+                .ok_or_else(|| invalid(line))?;
+            weights.0[term] = value.trim().parse().map_err(|_| invalid(line))?;
+        }
+
+        Ok(weights)
+    }
+}
+
+// men that have a clear run to the far side, no enemy piece anywhere in front of them
+fn is_runaway(manager: &Manager, row: usize, column: usize, forward: isize) -> bool {
+    let size = manager.size as isize;
+    let (row, column) = (row as isize, column as isize);
+    let mut distance = 1;
+
+    loop {
+        let ahead = row + forward * distance;
+        if ahead < 0 || ahead >= size {
+            return true;
+        }
+
+        let first = (column - distance).max(0);
+        let last = (column + distance).min(size - 1);
+
+        if (first..=last).any(|x| {
+            let piece = manager.board[(ahead * size + x) as usize];
+            !piece.is_empty() && !piece.match_piece(&manager.board[(row * size + column) as usize])
+        }) {
+            return false;
+        }
+
+        distance += 1;
+    }
+}
+
+// the terms for the side to move and then for the other side
+pub fn eval_terms(manager: &Manager) -> [[i32; TERMS]; 2] {
+    let size = manager.size;
+    let side = manager.current_side();
+    let mut terms = [[0; TERMS]; 2];
+
+    for (index, piece) in manager.board.iter().enumerate() {
+        if piece.is_empty() {
+            continue;
+        }
+
+        let (row, column) = (index / size, index % size);
+        let own = match piece.match_piece(&side) {
+            true => 0,
+            false => 1,
+        };
+        let terms = &mut terms[own];
+
+        // blue starts at the bottom and moves up the board
+        let (advance, forward) = match piece.is_blue() {
+            true => (size - 1 - row, -1),
+            false => (row, 1),
+        };
+
+        if (size / 2 - 1..=size / 2).contains(&row) && (2..size - 2).contains(&column) {
+            terms[CENTRE] += 1;
+        }
+
+        terms[MOBILITY] += manager.mobility(index) as i32;
+
+        if piece.is_king() == Some(true) {
+            terms[KING] += 1;
+            continue;
+        }
+
+        terms[MAN] += 1;
+        terms[TEMPO] += advance as i32;
+
+        if advance == 0 {
+            terms[BACK_RANK] += 1;
+        }
+
+        if advance >= size / 2 && is_runaway(manager, row, column, forward) {
+            terms[RUNAWAY] += 1;
+        }
+    }
+
+    terms
+}
+
+// score of the position for the side to move
+pub fn evaluate(manager: &Manager) -> i32 {
+    evaluate_with(manager, &Weights::default())
+}
+
+pub fn evaluate_with(manager: &Manager, weights: &Weights) -> i32 {
+    let [own, other] = eval_terms(manager);
+    let score = (0..TERMS)
+        .map(|x| weights.0[x] * (own[x] - other[x]))
+        .sum::<i32>();
+
+    // in giveaway having less material is what you want
+    if manager.variant.is_losing() {
+        -score
+    } else {
+        score
+    }
+}
+
+// the evaluation taken apart, each term weighted for each side so that the side to move
+// minus the other side adds up to the score
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    pub sides: [Piece; 2], // the side to move first
+    pub contributions: [[i32; TERMS]; 2],
+    pub score: i32,
+}
+
+impl Explanation {
+    pub fn total(&self, side: usize) -> i32 {
+        self.contributions[side].iter().sum()
+    }
+
+    // a row per term with blue in the first column
+    pub fn lines(&self) -> Vec<String> {
+        let [blue, red] = match self.sides[0].is_blue() {
+            true => [0, 1],
+            false => [1, 0],
+        };
+
+        let mut lines = vec![format!("{:<10}{:>8}{:>8}", "term", "blue", "red")];
+
+        for (term, name) in TERM_NAMES.iter().enumerate() {
+            lines.push(format!(
+                "{:<10}{:>8}{:>8}",
+                name, self.contributions[blue][term], self.contributions[red][term]
+            ));
+        }
+
+        lines.push(format!(
+            "{:<10}{:>8}{:>8}",
+            "total",
+            self.total(blue),
+            self.total(red)
+        ));
+        lines.push(format!(
+            "score {:+} for {}",
+            self.score,
+            match self.sides[0].is_blue() {
+                true => "blue",
+                false => "red",
+            }
+        ));
+
+        lines
+    }
+}
+
+pub fn evaluate_explained(manager: &Manager) -> Explanation {
+    evaluate_explained_with(manager, &Weights::default())
+}
+
+pub fn evaluate_explained_with(manager: &Manager, weights: &Weights) -> Explanation {
+    let side = manager.current_side();
+    let sign = match manager.variant.is_losing() {
+        true => -1,
+        false => 1,
+    };
+
+    let contributions = eval_terms(manager).map(|terms| {
+        let mut weighted = [0; TERMS];
+        for x in 0..TERMS {
+            weighted[x] = sign * weights.0[x] * terms[x];
+        }
+        weighted
+    });
+
+    Explanation {
+        sides: [side, side.opposite()],
+        contributions,
+        score: evaluate_with(manager, weights),
+    }
+}
+
+fn terminal_score(manager: &Manager, ply: i32) -> i32 {
+    if manager.winner.is_empty() {
+        0
+    } else if manager.winner.match_piece(&manager.current_side()) {
+        WIN_SCORE - ply
+    } else {
+        ply - WIN_SCORE
+    }
+}
+
+#[derive(Copy, Clone, Eq, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub quiescence_nodes: u64, // also counted in `nodes`
+    pub cutoffs: u64,
+    pub first_move_cutoffs: u64, // cutoffs that came from the first move tried
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    pub seldepth: u32, // deepest ply reached, quiescence included
+    pub reductions: u64,
+    pub researches: u64, // windows and reductions that were wrong and searched again
+}
+
+impl SearchStats {
+    pub fn add(&mut self, other: SearchStats) {
+        self.nodes += other.nodes;
+        self.quiescence_nodes += other.quiescence_nodes;
+        self.cutoffs += other.cutoffs;
+        self.first_move_cutoffs += other.first_move_cutoffs;
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+        self.seldepth = self.seldepth.max(other.seldepth);
+        self.reductions += other.reductions;
+        self.researches += other.researches;
+    }
+
+    pub fn tt_hit_rate(&self) -> f64 {
+        self.tt_hits as f64 / self.tt_probes.max(1) as f64
+    }
+
+    // how often the move ordering got the cutoff first time, between 0 and 1
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        self.first_move_cutoffs as f64 / self.cutoffs.max(1) as f64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Move,
+    pub score: i32,         // for the side to move, 0 when the move came from the book
+    pub depth: u8,          // of the last iteration that finished
+    pub lines: Vec<Line>,   // best first, `multi_pv` of them if there are enough moves
+    pub stats: SearchStats, // added up over all the threads
+}
+
+// progress of the main search thread, the helpers dont report
+#[derive(Clone, Debug, PartialEq)]
+pub struct Iteration {
+    pub depth: u8,
+    pub score: i32,
+    pub stats: SearchStats, // of the main thread so far
+    pub elapsed: Duration,
+    pub branching_factor: f64, // nodes this iteration took over the ones the last took
+    pub pv: Vec<Move>,
+}
+
+impl Iteration {
+    pub fn nodes_per_second(&self) -> u64 {
+        (self.stats.nodes as f64 / self.elapsed.as_secs_f64().max(1e-6)) as u64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchEvent {
+    Iteration(Iteration),
+    Finished(SearchStats, Duration), // added up over all the threads
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub score: i32,
+    pub moves: Vec<Move>, // the principal variation, starting with the move itself
+}
+
+// move ordering scores, anything below the killers comes from the history table
+const HASH_MOVE_ORDER: u32 = u32::MAX;
+const CAPTURE_ORDER: u32 = 1 << 30; // plus the number of pieces taken
+const KILLER_ORDER: u32 = 1 << 29;
+
+// mate scores are stored as distances from the position instead of from the root
+fn score_to_table(score: i32, ply: i32) -> i32 {
+    match score {
+        x if x > WIN_SCORE - 1000 => x + ply,
+        x if x < 1000 - WIN_SCORE => x - ply,
+        x => x,
+    }
+}
+
+fn score_from_table(score: i32, ply: i32) -> i32 {
+    match score {
+        x if x > WIN_SCORE - 1000 => x - ply,
+        x if x < 1000 - WIN_SCORE => x + ply,
+        x => x,
+    }
+}
+
+struct Searcher<'a> {
+    options: &'a SearchOptions,
+    stats: SearchStats,
+    table: &'a TranspositionTable,
+    stop: &'a AtomicBool,     // the callers, only ever read
+    finished: &'a AtomicBool, // out of time or the main search is done, for this search alone
+    deadline: Option<Instant>,
+    killers: Vec<[Option<(usize, usize)>; 2]>, // two quiet moves per ply that caused a cutoff
+    history: Vec<u32>,                         // from and to squares of quiet cutoffs
+    squares: usize,
+    started: Instant,
+    events: Option<&'a Sender<SearchEvent>>,
+    tree: Option<Vec<String>>, // the dump, only written to while `tracing`
+    tracing: bool,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(
+        options: &'a SearchOptions,
+        table: &'a TranspositionTable,
+        (stop, finished): (&'a AtomicBool, &'a AtomicBool),
+        squares: usize,
+    ) -> Self {
+        Searcher {
+            options,
+            stats: SearchStats::default(),
+            table,
+            stop,
+            finished,
+            deadline: None,
+            killers: vec![[None; 2]; MAX_PLY as usize + 1],
+            history: vec![0; squares * squares],
+            squares,
+            started: Instant::now(),
+            events: None,
+            tree: None,
+            tracing: false,
+        }
+    }
+
+    // one line per move searched, indented by ply, with its score and the window it had
+    fn trace(
+        &mut self,
+        manager: &Manager,
+        traced: &Move,
+        ply: i32,
+        quiescence: bool,
+    ) -> Option<usize> {
+        let tree = self.tree.as_mut().filter(|_| self.tracing)?;
+
+        tree.push(format!(
+            "{}{}{}",
+            "  ".repeat(ply as usize),
+            pdn::move_to_string(manager, traced),
+            if quiescence { " q" } else { "" }
+        ));
+
+        Some(tree.len() - 1)
+    }
+
+    fn trace_score(&mut self, line: Option<usize>, score: i32, alpha: i32, beta: i32) {
+        if let (Some(tree), Some(line)) = (self.tree.as_mut(), line) {
+            tree[line].push_str(&format!(" {} [{}, {}]", score, alpha, beta));
+        }
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed) || self.finished.load(Ordering::Relaxed)
+    }
+
+    fn evaluate(&self, manager: &Manager) -> i32 {
+        match &self.options.network {
+            Some(network) if manager.variant == Variant::Standard => network.evaluate(manager),
+            _ => evaluate_with(manager, &self.options.weights),
+        }
+    }
+
+    fn probe(&self, manager: &Manager, ply: i32) -> Option<i32> {
+        let outcome = self.options.egdb.as_ref()?.probe(manager)?;
+
+        Some(match outcome {
+            Outcome::Win(distance) => WIN_SCORE - ply - distance as i32,
+            Outcome::Loss(distance) => ply + distance as i32 - WIN_SCORE,
+            Outcome::Draw => 0,
+        })
+    }
+
+    // hash move, then the captures taking the most, the killers and the rest by history
+    fn order_moves(&self, moves: &mut [Move], hash_move: Option<(u8, u8)>, ply: i32) {
+        let killers = self.killers[ply as usize];
+
+        moves.sort_by_cached_key(|x| {
+            let order = if Some(tt::move_key(x)) == hash_move {
+                HASH_MOVE_ORDER
+            } else if !x.kills.is_empty() {
+                CAPTURE_ORDER + x.kills.len() as u32
+            } else if let Some(killer) = killers.iter().position(|y| *y == Some((x.start, x.end))) {
+                KILLER_ORDER - killer as u32
+            } else {
+                self.history[x.start * self.squares + x.end].min(KILLER_ORDER - 2)
+            };
+
+            std::cmp::Reverse(order)
+        });
+    }
+
+    fn record_cutoff(&mut self, cutoff_move: &Move, depth: u8, ply: i32, first: bool) {
+        self.stats.cutoffs += 1;
+        if first {
+            self.stats.first_move_cutoffs += 1;
+        }
+
+        if !cutoff_move.kills.is_empty() || cutoff_move.huff {
+            return;
+        }
+
+        let killers = &mut self.killers[ply as usize];
+        let key = Some((cutoff_move.start, cutoff_move.end));
+        if killers[0] != key {
+            killers[1] = killers[0];
+            killers[0] = key;
+        }
+
+        let history = &mut self.history[cutoff_move.start * self.squares + cutoff_move.end];
+        *history = history.saturating_add(depth as u32 * depth as u32);
     }
 
-    generated_moves
+    fn negamax(
+        &mut self,
+        manager: &mut Manager,
+        depth: u8,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> i32 {
+        // the score is thrown away once the search is stopped
+        if self.stopped() {
+            return 0;
+        }
+
+        self.stats.nodes += 1;
+        self.stats.seldepth = self.stats.seldepth.max(ply as u32);
+
+        if self.stats.nodes.is_multiple_of(1024)
+            && self.deadline.is_some_and(|x| Instant::now() >= x)
+        {
+            self.finished.store(true, Ordering::Relaxed);
+        }
+
+        if manager.gameover {
+            return terminal_score(manager, ply);
+        }
+
+        if let Some(score) = self.probe(manager, ply) {
+            return score;
+        }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return match self.options.quiescence {
+                true => self.quiescence(manager, alpha, beta, ply, THREAT_PLIES),
+                false => self.evaluate(manager),
+            };
+        }
+
+        let key = manager.hash();
+        let entry = self.table.probe(key);
+
+        self.stats.tt_probes += 1;
+        if entry.is_some() {
+            self.stats.tt_hits += 1;
+        }
+
+        if let Some(entry) = entry.filter(|x| x.depth >= depth) {
+            let score = score_from_table(entry.score, ply);
+
+            match entry.bound {
+                Bound::Exact => return score,
+                Bound::Lower if score >= beta => return score,
+                Bound::Upper if score <= alpha => return score,
+                _ => {}
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best = -WIN_SCORE;
+        let mut best_move = None;
+
+        let mut possible_moves = manager.legal_moves();
+        self.order_moves(&mut possible_moves, entry.and_then(|x| x.best), ply);
+
+        for (index, possible_move) in possible_moves.into_iter().enumerate() {
+            let key = tt::move_key(&possible_move);
+            let score = match index {
+                0 => self.search_move(
+                    manager,
+                    possible_move.clone(),
+                    depth - 1,
+                    alpha,
+                    beta,
+                    ply + 1,
+                ),
+                _ => self.search_later_move(
+                    manager,
+                    &possible_move,
+                    index,
+                    depth,
+                    (alpha, beta),
+                    ply + 1,
+                ),
+            };
+
+            if score > best {
+                best = score;
+                best_move = Some(key);
+            }
+            alpha = alpha.max(score);
+
+            if alpha >= beta {
+                self.record_cutoff(&possible_move, depth, ply, index == 0);
+                break;
+            }
+        }
+
+        if self.stopped() {
+            return best;
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.store(Entry {
+            key,
+            depth,
+            score: score_to_table(best, ply),
+            bound,
+            best: best_move,
+        });
+
+        best
+    }
+
+    // carries on while a capture is on the board, either for the side to move or threatened
+    // by the other side, so the evaluation is never taken halfway through an exchange
+    fn quiescence(
+        &mut self,
+        manager: &mut Manager,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+        threat_plies: u8,
+    ) -> i32 {
+        if manager.gameover {
+            return terminal_score(manager, ply);
+        }
+
+        if let Some(score) = self.probe(manager, ply) {
+            return score;
+        }
+
+        let must_capture = manager.must_capture();
+        let threatened =
+            threat_plies > 0 && manager.threatens_capture(manager.current_side().opposite());
+
+        if ply >= MAX_PLY || !(must_capture || threatened) {
+            return self.evaluate(manager);
+        }
+
+        let threat_plies = match must_capture {
+            true => threat_plies,
+            false => threat_plies - 1,
+        };
+        let mut best = -WIN_SCORE;
+
+        for possible_move in manager.legal_moves() {
+            self.stats.nodes += 1;
+            self.stats.quiescence_nodes += 1;
+            self.stats.seldepth = self.stats.seldepth.max(ply as u32 + 1);
+
+            let same_side = possible_move.huff;
+            let line = self.trace(manager, &possible_move, ply, true);
+
+            manager.play_move(possible_move);
+            let score = match same_side {
+                true => self.quiescence(manager, alpha, beta, ply + 1, threat_plies),
+                false => -self.quiescence(manager, -beta, -alpha, ply + 1, threat_plies),
+            };
+            manager.undo_move();
+
+            self.trace_score(line, score, alpha, beta);
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best
+    }
+
+    // a move after the first is tried with a null window, and a quiet one late in the ordering
+    // a ply shallower too, and only searched properly when it beats the best so far
+    fn search_later_move(
+        &mut self,
+        manager: &mut Manager,
+        possible_move: &Move,
+        index: usize,
+        depth: u8,
+        (alpha, beta): (i32, i32),
+        ply: i32,
+    ) -> i32 {
+        let quiet =
+            possible_move.kills.is_empty() && !possible_move.huff && !possible_move.should_king;
+
+        if self.options.lmr && quiet && index >= LMR_MOVES && depth >= LMR_DEPTH {
+            self.stats.reductions += 1;
+
+            let score = self.search_move(
+                manager,
+                possible_move.clone(),
+                depth - 2,
+                alpha,
+                alpha + 1,
+                ply,
+            );
+
+            if score <= alpha {
+                return score;
+            }
+            self.stats.researches += 1;
+        }
+
+        if self.options.pvs {
+            let score = self.search_move(
+                manager,
+                possible_move.clone(),
+                depth - 1,
+                alpha,
+                alpha + 1,
+                ply,
+            );
+
+            if score <= alpha || score >= beta {
+                return score;
+            }
+            self.stats.researches += 1;
+        }
+
+        self.search_move(manager, possible_move.clone(), depth - 1, alpha, beta, ply)
+    }
+
+    // plays the move and scores it for the side that played it
+    fn search_move(
+        &mut self,
+        manager: &mut Manager,
+        possible_move: Move,
+        depth: u8,
+        alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> i32 {
+        // a huff is followed by another move from the same side
+        let same_side = possible_move.huff;
+        let line = self.trace(manager, &possible_move, ply - 1, false);
+
+        manager.play_move(possible_move);
+        let score = match same_side {
+            true => self.negamax(manager, depth, alpha, beta, ply),
+            false => -self.negamax(manager, depth, -beta, -alpha, ply),
+        };
+        manager.undo_move();
+
+        self.trace_score(line, score, alpha, beta);
+
+        score
+    }
+}
+
+// the same seed in the same position always makes the same choices, so a game can be
+// played again move for move. without a seed every search is different
+fn seeded_rng(seed: Option<u64>, hash: u64) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed ^ hash),
+        None => StdRng::seed_from_u64(thread_rng().gen()),
+    }
 }
 
-pub fn find_best_move(manager: &Manager) -> Move {
+// none when there is no legal move to make
+pub fn find_best_move(manager: &Manager) -> Option<Move> {
+    find_best_move_with(manager, &SearchOptions::default())
+}
+
+pub fn find_best_move_with(manager: &Manager, options: &SearchOptions) -> Option<Move> {
+    search(manager, options).map(|x| x.best_move)
+}
+
+pub fn search(manager: &Manager, options: &SearchOptions) -> Option<SearchResult> {
+    search_until(manager, options, &AtomicBool::new(false))
+}
+
+// gives up once `stop` is set from somewhere else, the last full iteration is still used.
+// `stop` is left as it was, the search only ever reads it
+pub fn search_until(
+    manager: &Manager,
+    options: &SearchOptions,
+    stop: &AtomicBool,
+) -> Option<SearchResult> {
+    let mut rng = seeded_rng(options.seed, manager.hash());
+
     // finds the best possible move
+    if let Some(book) = &options.book {
+        if let Some(book_move) = book.probe(manager, options.book_selection, &mut rng) {
+            return Some(SearchResult {
+                best_move: book_move.clone(),
+                score: 0,
+                depth: 0,
+                lines: vec![Line {
+                    score: 0,
+                    moves: vec![book_move],
+                }],
+                stats: SearchStats::default(),
+            });
+        }
+    }
+
+    // a weakened engine needs a proper score for every move to pick from
+    let weakened;
+    let options = match options.weakening {
+        Some(_) => {
+            weakened = SearchOptions {
+                multi_pv: usize::MAX,
+                ..options.clone()
+            };
+            &weakened
+        }
+        None => options,
+    };
+    let deadline = options.time.map(|x| Instant::now() + x);
+
     let mut my_manager: Manager = manager.to_owned();
-    let possible_moves = generate_all_moves(&my_manager);
+    // both sides are played by hand here, otherwise play_move would call back into the ai
+    my_manager.players = [Player::User, Player::User];
+
+    let mut possible_moves = my_manager.legal_moves();
+
+    if possible_moves.is_empty() {
+        return None;
+    }
+
+    // shuffled so equally good moves are picked at random
+    possible_moves.shuffle(&mut rng);
+
+    let squares = manager.board.len();
+    let depth = options.depth.max(1);
+    let table = TranspositionTable::new(options.hash_entries);
+
+    // lazy smp, the helpers search the same position in their own order and mostly help
+    // by filling the hash table. the move comes from the main thread alone, though with
+    // helpers the timing of the threads still makes it vary even with a seed
+    let helper_seeds: Vec<u64> = (1..options.threads.max(1)).map(|_| rng.gen()).collect();
+    let finished = AtomicBool::new(false);
+
+    let (scores, depth, stats) = thread::scope(|scope| {
+        let helpers: Vec<_> = helper_seeds
+            .iter()
+            .enumerate()
+            .map(|(helper, seed)| {
+                let mut manager = my_manager.clone();
+                let mut moves = possible_moves.clone();
+                let table = &table;
+                let finished = &finished;
+
+                scope.spawn(move || {
+                    moves.shuffle(&mut StdRng::seed_from_u64(*seed));
+
+                    // every other helper goes a ply deeper so they dont all do the same work
+                    let mut searcher = Searcher::new(options, table, (stop, finished), squares);
+                    let depths = 1..=depth + ((helper + 1) % 2) as u8;
+                    iterative_deepening(&mut searcher, &mut manager, &mut moves, depths, None);
+
+                    searcher.stats
+                })
+            })
+            .collect();
+
+        let mut searcher = Searcher::new(options, &table, (stop, &finished), squares);
+        searcher.events = options.events.as_ref();
+        searcher.tree = options.tree_dump.as_ref().map(|_| Vec::new());
+
+        let (scores, depth) = iterative_deepening(
+            &mut searcher,
+            &mut my_manager,
+            &mut possible_moves,
+            1..=depth,
+            deadline,
+        );
+        finished.store(true, Ordering::Relaxed);
 
-    let mut rng = thread_rng();
-    possible_moves[rng.gen_range(0..possible_moves.len())].clone()
+        let mut stats = searcher.stats;
+        for helper in helpers {
+            stats.add(helper.join().unwrap());
+        }
+
+        if let (Some(path), Some(tree)) = (&options.tree_dump, &searcher.tree) {
+            if let Err(error) = fs::write(path, tree.join("\n")) {
+                eprintln!("couldnt write the search tree to {}: {}", path, error);
+            }
+        }
+
+        if let Some(events) = &options.events {
+            // nobody listening any more is fine
+            let _ = events.send(SearchEvent::Finished(stats, searcher.started.elapsed()));
+        }
+
+        (scores, depth, stats)
+    });
+
+    let lines: Vec<Line> = possible_moves
+        .iter()
+        .zip(&scores)
+        .take(options.multi_pv.max(1))
+        .map(|(first, score)| Line {
+            score: *score,
+            moves: principal_variation(&table, &my_manager, first, depth as usize + 1),
+        })
+        .collect();
+
+    let chosen = match options.weakening {
+        Some(weakening) if !lines.is_empty() => weakening.choose(&lines, &mut rng),
+        _ => 0,
+    };
+
+    Some(SearchResult {
+        best_move: possible_moves.swap_remove(chosen),
+        score: scores.get(chosen).copied().unwrap_or(0),
+        depth,
+        lines,
+        stats,
+    })
+}
+
+// follows the best moves in the hash table on from `first`
+fn principal_variation(
+    table: &TranspositionTable,
+    manager: &Manager,
+    first: &Move,
+    length: usize,
+) -> Vec<Move> {
+    let mut manager = manager.clone();
+    let mut line = vec![first.clone()];
+    manager.play_move(first.clone());
+
+    while line.len() < length && !manager.gameover {
+        let next = table
+            .probe(manager.hash())
+            .and_then(|x| x.best)
+            .and_then(|best| {
+                manager
+                    .legal_moves()
+                    .into_iter()
+                    .find(|x| tt::move_key(x) == best)
+            });
+
+        match next {
+            Some(next) => {
+                manager.play_move(next.clone());
+                line.push(next);
+            }
+            None => break,
+        }
+    }
+
+    line
+}
+
+// one iteration over the root moves, scored with their index. a move failing high ends it
+// early since the window has to be widened anyway. none if the search was stopped
+fn search_root(
+    searcher: &mut Searcher,
+    manager: &mut Manager,
+    moves: &[Move],
+    depth: u8,
+    (low, high): (i32, i32),
+) -> Option<Vec<(i32, usize)>> {
+    let lines = searcher.options.multi_pv.max(1);
+    let mut scored: Vec<(i32, usize)> = Vec::new();
+    let mut best: Vec<i32> = Vec::new(); // sorted, the best `lines` scores so far
+
+    for (index, possible_move) in moves.iter().enumerate() {
+        // a move only has to be searched exactly if it can get into the lines
+        let alpha = match best.len() >= lines {
+            true => best[lines - 1].max(low),
+            false => low,
+        };
+
+        // with the lines filled the rest only have to be shown not to get in
+        let score = match searcher.options.pvs && best.len() >= lines {
+            true => {
+                let score = searcher.search_move(
+                    manager,
+                    possible_move.clone(),
+                    depth - 1,
+                    alpha,
+                    alpha + 1,
+                    1,
+                );
+
+                match score > alpha && score < high {
+                    true => {
+                        searcher.stats.researches += 1;
+                        searcher.search_move(
+                            manager,
+                            possible_move.clone(),
+                            depth - 1,
+                            alpha,
+                            high,
+                            1,
+                        )
+                    }
+                    false => score,
+                }
+            }
+            false => {
+                searcher.search_move(manager, possible_move.clone(), depth - 1, alpha, high, 1)
+            }
+        };
+
+        if searcher.stopped() {
+            return None;
+        }
+
+        scored.push((score, index));
+
+        if score >= high {
+            break;
+        }
+
+        let position = best.partition_point(|x| *x >= score);
+        best.insert(position, score);
+        best.truncate(lines);
+    }
+
+    Some(scored)
+}
+
+// each iteration starts with the best moves of the one before, `moves` is left sorted by
+// score. returns the scores, exact for the first `multi_pv` moves, and the depth of the last
+// iteration that wasnt stopped or didnt run out of time
+fn iterative_deepening(
+    searcher: &mut Searcher,
+    manager: &mut Manager,
+    moves: &mut Vec<Move>,
+    depths: RangeInclusive<u8>,
+    deadline: Option<Instant>,
+) -> (Vec<i32>, u8) {
+    let lines = searcher.options.multi_pv.max(1);
+    let mut scores = Vec::new();
+    let mut completed = 0;
+    let mut last_nodes = 0; // taken by the iteration before, 0 for none
+
+    for depth in depths {
+        let start_nodes = searcher.stats.nodes;
+
+        if let Some(tree) = searcher.tree.as_mut() {
+            searcher.tracing = depth <= TREE_DUMP_DEPTH;
+
+            if searcher.tracing {
+                tree.push(format!("depth {}", depth));
+            }
+        }
+
+        // the lines all need exact scores, so only a single one gets a window
+        let mut window = match scores.first() {
+            Some(previous) if searcher.options.aspiration && lines == 1 => {
+                (previous - ASPIRATION_WINDOW, previous + ASPIRATION_WINDOW)
+            }
+            _ => (-WIN_SCORE - 1, WIN_SCORE + 1),
+        };
+
+        // outside the window the best score is only a bound, that side is opened up and the
+        // iteration searched again
+        let mut scored = loop {
+            let scored = match search_root(searcher, manager, moves, depth, window) {
+                Some(scored) => scored,
+                None => return (scores, completed),
+            };
+
+            match scored.iter().map(|x| x.0).max() {
+                Some(best) if best <= window.0 => window.0 = -WIN_SCORE - 1,
+                Some(best) if best >= window.1 => window.1 = WIN_SCORE + 1,
+                _ => break scored,
+            }
+            searcher.stats.researches += 1;
+        };
+
+        // stable so a move only overtakes one with a higher score
+        scored.sort_by_key(|x| std::cmp::Reverse(x.0));
+        *moves = scored.iter().map(|x| moves[x.1].clone()).collect();
+        scores = scored.iter().map(|x| x.0).collect();
+        completed = depth;
+
+        // there is always a move to play once the first iteration is done
+        searcher.deadline = deadline;
+
+        let nodes = searcher.stats.nodes - start_nodes;
+
+        if let Some(events) = searcher.events {
+            let _ = events.send(SearchEvent::Iteration(Iteration {
+                depth,
+                score: scores.first().copied().unwrap_or(0),
+                stats: searcher.stats,
+                elapsed: searcher.started.elapsed(),
+                branching_factor: match last_nodes {
+                    0 => 0f64,
+                    last => nodes as f64 / last as f64,
+                },
+                pv: match moves.first() {
+                    Some(first) => {
+                        principal_variation(searcher.table, manager, first, depth as usize + 1)
+                    }
+                    None => Vec::new(),
+                },
+            }));
+        }
+
+        last_nodes = nodes;
+    }
+
+    (scores, completed)
+}
+
+#[derive(Copy, Clone, Eq, Debug, Default, PartialEq)]
+pub enum Playout {
+    Random,
+    #[default]
+    Heuristic, // greedy on the evaluation, with some random moves mixed in
+}
+
+#[derive(Clone, Debug)]
+pub struct MctsOptions {
+    pub iterations: Option<u32>,
+    pub time: Option<Duration>, // stops at whichever budget runs out first
+    pub exploration: f64,
+    pub playout: Playout,
+    pub playout_plies: usize, // playouts still going after this many moves are judged by material
+    pub seed: Option<u64>,
+}
+
+impl Default for MctsOptions {
+    fn default() -> Self {
+        MctsOptions {
+            iterations: Some(2000),
+            time: None,
+            exploration: std::f64::consts::SQRT_2,
+            playout: Playout::default(),
+            playout_plies: 60,
+            seed: None,
+        }
+    }
+}
+
+struct Node {
+    hash: u64,
+    mover: Piece, // the side that played the move leading here
+    played: Option<Move>,
+    untried: Vec<Move>,
+    children: Vec<Node>,
+    visits: u32,
+    reward: f64, // from the point of view of `mover`
+}
+
+impl Node {
+    fn new(manager: &Manager, mover: Piece, played: Option<Move>, rng: &mut StdRng) -> Self {
+        let mut untried = match manager.gameover {
+            true => Vec::new(),
+            false => manager.legal_moves(),
+        };
+        untried.shuffle(rng);
+
+        Node {
+            hash: manager.hash(),
+            mover,
+            played,
+            untried,
+            children: Vec::new(),
+            visits: 0,
+            reward: 0f64,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32, exploration: f64) -> f64 {
+        self.reward / self.visits as f64
+            + exploration * ((parent_visits as f64).ln() / self.visits as f64).sqrt()
+    }
+
+    fn update(&mut self, blue_reward: f64) {
+        self.visits += 1;
+        self.reward += match self.mover.is_blue() {
+            true => blue_reward,
+            false => 1f64 - blue_reward,
+        };
+    }
+}
+
+// 1 for a blue win, 0 for a red win and a half for a draw
+fn blue_reward(manager: &Manager) -> f64 {
+    let score = match manager.gameover {
+        true => match manager.winner {
+            Piece::Blue(_) => 1,
+            Piece::Red(_) => -1,
+            Piece::Empty => 0,
+        },
+        false => match manager.current_side().is_blue() {
+            true => evaluate(manager).signum(),
+            false => -evaluate(manager).signum(),
+        },
+    };
+
+    (score + 1) as f64 / 2f64
+}
+
+// monte carlo tree search, the tree is kept between moves and picked up again from
+// the position the opponent left
+pub struct Mcts {
+    pub options: MctsOptions,
+    root: Option<Node>,
+    rng: StdRng,
+}
+
+impl Mcts {
+    pub fn new(options: MctsOptions) -> Self {
+        Mcts {
+            rng: seeded_rng(options.seed, 0),
+            options,
+            root: None,
+        }
+    }
+
+    // playouts that went through the current root
+    pub fn visits(&self) -> u32 {
+        self.root.as_ref().map_or(0, |x| x.visits)
+    }
+
+    // looks for the position in the last tree, two moves down at most
+    fn reuse(&mut self, hash: u64) -> Option<Node> {
+        let mut root = self.root.take()?;
+
+        if root.hash == hash {
+            return Some(root);
+        }
+
+        for mut child in root.children.drain(..) {
+            if child.hash == hash {
+                return Some(child);
+            }
+
+            if let Some(index) = child.children.iter().position(|x| x.hash == hash) {
+                return Some(child.children.swap_remove(index));
+            }
+        }
+
+        None
+    }
+
+    // none when there is no legal move to make, like the alpha beta search
+    pub fn find_best_move(&mut self, manager: &Manager) -> Option<Move> {
+        if manager.gameover || manager.legal_moves().is_empty() {
+            return None;
+        }
+
+        let mut my_manager = manager.to_owned();
+        my_manager.players = [Player::User, Player::User];
+
+        let mut root = match self.reuse(manager.hash()) {
+            Some(root) => root,
+            None => Node::new(&my_manager, Piece::Empty, None, &mut self.rng),
+        };
+
+        let start = Instant::now();
+        let mut iterations = 0;
+
+        while self.options.iterations.is_none_or(|x| iterations < x)
+            && self.options.time.is_none_or(|x| start.elapsed() < x)
+        {
+            // children are updated by their parent, the root has none
+            self.iterate(&mut root, &mut my_manager.clone());
+            root.visits += 1;
+            iterations += 1;
+
+            // without any budget a single playout is all you get
+            if self.options.iterations.is_none() && self.options.time.is_none() {
+                break;
+            }
+        }
+
+        let best_move = match root.children.iter().max_by_key(|x| x.visits) {
+            Some(child) => child.played.clone(),
+            None => my_manager.legal_moves().into_iter().next(),
+        };
+
+        self.root = Some(root);
+        best_move
+    }
+
+    fn iterate(&mut self, node: &mut Node, manager: &mut Manager) -> f64 {
+        let reward = if let Some(possible_move) = node.untried.pop() {
+            let mover = manager.current_side();
+            manager.play_move(possible_move.clone());
+
+            let mut child = Node::new(manager, mover, Some(possible_move), &mut self.rng);
+            let reward = self.playout(manager);
+            child.update(reward);
+            node.children.push(child);
+
+            reward
+        } else if node.children.is_empty() {
+            blue_reward(manager)
+        } else {
+            let visits = node.visits;
+            let exploration = self.options.exploration;
+            let child = node
+                .children
+                .iter_mut()
+                .max_by(|a, b| {
+                    a.uct(visits, exploration)
+                        .total_cmp(&b.uct(visits, exploration))
+                })
+                .unwrap();
+
+            manager.play_move(child.played.clone().unwrap());
+            let reward = self.iterate(child, manager);
+            child.update(reward);
+
+            reward
+        };
+
+        reward
+    }
+
+    fn playout(&mut self, manager: &mut Manager) -> f64 {
+        for _ in 0..self.options.playout_plies {
+            if manager.gameover {
+                break;
+            }
+
+            let mut possible_moves = manager.legal_moves();
+
+            let index = match self.options.playout {
+                Playout::Heuristic if !self.rng.gen_bool(0.1) => {
+                    let side = manager.current_side();
+
+                    (0..possible_moves.len())
+                        .map(|x| {
+                            manager.play_move(possible_moves[x].clone());
+                            let score = match manager.current_side().match_piece(&side) {
+                                true => evaluate(manager),
+                                false => -evaluate(manager),
+                            };
+                            manager.undo_move();
+
+                            // random noise breaks up the ties between quiet moves
+                            (score * 8 + self.rng.gen_range(0..8), x)
+                        })
+                        .max()
+                        .unwrap()
+                        .1
+                }
+                _ => self.rng.gen_range(0..possible_moves.len()),
+            };
+
+            manager.play_move(possible_moves.swap_remove(index));
+        }
+
+        blue_reward(manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        evaluate, evaluate_explained, find_best_move, find_best_move_with, search, search_until,
+        Difficulty, Mcts, MctsOptions, SearchEvent, SearchOptions, TERMS, TERM_NAMES,
+        TREE_DUMP_DEPTH,
+    };
+    use crate::board::{Manager, Piece, Variant};
+    use crate::pdn;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_evaluate_giveaway() {
+        let mut manager = Manager::new();
+        manager.board[62] = Piece::Empty;
+
+        let mut giveaway = Manager::with_variant(Variant::Giveaway);
+        giveaway.board[62] = Piece::Empty;
+
+        assert!(evaluate(&manager) < 0);
+        assert_eq!(evaluate(&giveaway), -evaluate(&manager));
+    }
+
+    #[test]
+    fn test_evaluate_explained() {
+        let mut manager = Manager::new();
+        manager.board[62] = Piece::Empty;
+
+        let explanation = evaluate_explained(&manager);
+        assert_eq!(explanation.score, evaluate(&manager));
+        assert_eq!(
+            explanation.total(0) - explanation.total(1),
+            explanation.score
+        );
+
+        // blue still has its seven opening moves, the missing man is from the back rank
+        let mobility = TERM_NAMES.iter().position(|x| *x == "mobility").unwrap();
+        assert_eq!(explanation.contributions[0][mobility], 7 * 2);
+        assert_eq!(explanation.lines().len(), TERMS + 3);
+    }
+
+    #[test]
+    fn test_find_best_move_is_legal() {
+        let manager = Manager::with_variant(Variant::Giveaway);
+        let best_move = find_best_move(&manager).unwrap();
+
+        assert!(manager.legal_moves().contains(&best_move));
+    }
+
+    #[test]
+    fn test_mcts_reuses_tree() {
+        let mut manager = Manager::new();
+        let mut mcts = Mcts::new(MctsOptions {
+            iterations: Some(200),
+            ..Default::default()
+        });
+
+        let best_move = mcts.find_best_move(&manager).unwrap();
+        assert!(manager.legal_moves().contains(&best_move));
+        assert_eq!(mcts.visits(), 200);
+
+        // the reply is already in the tree so the search carries on from there
+        manager.play_move(best_move);
+        let reply = manager.legal_moves().swap_remove(0);
+        manager.play_move(reply);
+
+        let best_move = mcts.find_best_move(&manager).unwrap();
+        assert!(manager.legal_moves().contains(&best_move));
+        assert!(mcts.visits() > 200);
+    }
+
+    #[test]
+    fn test_quiescence_sees_the_recapture() {
+        // blue has to take a man but red takes straight back
+        let mut manager = Manager::new();
+        let mut board = vec![Piece::Empty; 64];
+        board[44] = Piece::Blue(false);
+        board[35] = Piece::Red(false);
+        board[17] = Piece::Red(false);
+        board[8] = Piece::Red(false); // stops blue jumping on
+        board[58] = Piece::Blue(false);
+        board[3] = Piece::Red(false);
+        manager.set_position(board, Piece::Blue(false));
+
+        let options = SearchOptions {
+            depth: 1,
+            ..Default::default()
+        };
+        let result = search(&manager, &options).unwrap();
+        assert!(result.stats.quiescence_nodes > 0);
+
+        let flat = search(
+            &manager,
+            &SearchOptions {
+                quiescence: false,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!(flat.stats.quiescence_nodes, 0);
+        assert!(result.score < flat.score);
+    }
+
+    #[test]
+    fn test_move_ordering_stats() {
+        let manager = Manager::new();
+        let result = search(&manager, &SearchOptions::default()).unwrap();
+        let stats = result.stats;
+
+        assert!(manager.legal_moves().contains(&result.best_move));
+        assert!(stats.cutoffs > 0);
+        assert!(stats.first_move_cutoff_rate() > 0.5);
+    }
+
+    #[test]
+    fn test_lazy_smp() {
+        let manager = Manager::new();
+        let options = SearchOptions {
+            threads: 4,
+            ..Default::default()
+        };
+        let result = search(&manager, &options).unwrap();
+
+        assert!(manager.legal_moves().contains(&result.best_move));
+        assert_eq!(result.depth, options.depth);
+    }
+
+    #[test]
+    fn test_multi_pv() {
+        let manager = Manager::new();
+        let result = search(
+            &manager,
+            &SearchOptions {
+                depth: 4,
+                multi_pv: 3,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.lines[0].moves[0], result.best_move);
+        assert_eq!(result.lines[0].score, result.score);
+        assert!(result.lines.windows(2).all(|x| x[0].score >= x[1].score));
+        assert!(result.lines[0].moves.len() > 1);
+    }
+
+    #[test]
+    fn test_search_speedups() {
+        let manager = Manager::new();
+        let options = |on: bool, lmr: bool| SearchOptions {
+            depth: 7,
+            aspiration: on,
+            pvs: on,
+            lmr,
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let plain = search(&manager, &options(false, false)).unwrap();
+        let windows = search(&manager, &options(true, false)).unwrap();
+        let reduced = search(&manager, &options(true, true)).unwrap();
+
+        // the windows only change how the same score is found, reductions can change it
+        assert_eq!(windows.score, plain.score);
+        assert_eq!(plain.stats.researches + plain.stats.reductions, 0);
+        assert!(reduced.stats.reductions > 0);
+        assert!(reduced.stats.nodes < plain.stats.nodes);
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let manager = Manager::new();
+        let options = SearchOptions {
+            depth: 40,
+            time: Some(std::time::Duration::from_millis(200)),
+            ..Default::default()
+        };
+
+        let start = std::time::Instant::now();
+        let result = search(&manager, &options).unwrap();
+
+        assert!(start.elapsed().as_secs() < 2);
+        assert!(result.depth >= 1 && result.depth < 40);
+        assert!(manager.legal_moves().contains(&result.best_move));
+
+        // running out of time stops the search without touching the callers flag, the next
+        // search with it would be stopped straight away otherwise
+        let stop = AtomicBool::new(false);
+        let options = SearchOptions {
+            time: Some(std::time::Duration::from_millis(50)),
+            threads: 2,
+            ..options
+        };
+        assert!(search_until(&manager, &options, &stop).is_some());
+        assert!(!stop.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_no_legal_moves() {
+        // the blue man is walled in and cant take either red one
+        let manager = pdn::from_fen(Variant::Standard, "B:B1:W5,6,9,10").unwrap();
+        assert!(manager.legal_moves().is_empty());
+
+        assert!(search(&manager, &SearchOptions::default()).is_none());
+        assert!(find_best_move(&manager).is_none());
+        assert!(Mcts::new(MctsOptions::default())
+            .find_best_move(&manager)
+            .is_none());
+    }
+
+    #[test]
+    fn test_search_events() {
+        let manager = Manager::new();
+        let (sender, events) = std::sync::mpsc::channel();
+        let path = std::env::temp_dir().join("checkers_search_tree.txt");
+        let options = SearchOptions {
+            depth: 5,
+            events: Some(sender),
+            tree_dump: Some(path.to_str().unwrap().to_owned()),
+            ..Default::default()
+        };
+
+        let result = search(&manager, &options).unwrap();
+        let events: Vec<SearchEvent> = events.try_iter().collect();
+
+        // one event per iteration and one at the end
+        assert_eq!(events.len(), 6);
+
+        for (depth, event) in events.iter().take(5).enumerate() {
+            match event {
+                SearchEvent::Iteration(iteration) => {
+                    assert_eq!(iteration.depth as usize, depth + 1);
+                    assert!(iteration.stats.seldepth >= iteration.depth as u32);
+                    assert!(manager.legal_moves().contains(&iteration.pv[0]));
+                }
+                _ => panic!("expected an iteration"),
+            }
+        }
+
+        match &events[5] {
+            SearchEvent::Finished(stats, _) => {
+                assert_eq!(*stats, result.stats);
+                assert!(stats.tt_hits > 0 && stats.tt_hits <= stats.tt_probes);
+            }
+            _ => panic!("expected the end"),
+        }
+
+        // the dump stops at the depth its limited to
+        let tree = std::fs::read_to_string(&path).unwrap();
+        assert!(tree.contains(&format!("depth {}", TREE_DUMP_DEPTH)));
+        assert!(!tree.contains(&format!("depth {}", TREE_DUMP_DEPTH + 1)));
+        assert!(tree.lines().any(|x| x.starts_with("      ")));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_seeded_games_repeat() {
+        // weakened so the randomness decides a lot of the moves
+        let options = SearchOptions {
+            seed: Some(42),
+            ..Difficulty::Beginner.options()
+        };
+
+        let play = |options: &SearchOptions| {
+            let mut manager = Manager::new();
+            for _ in 0..16 {
+                if manager.gameover {
+                    break;
+                }
+                manager.play_move(find_best_move_with(&manager, options).unwrap());
+            }
+            manager.played_moves()
+        };
+
+        assert_eq!(play(&options), play(&options));
+
+        let mcts_options = MctsOptions {
+            iterations: Some(100),
+            seed: Some(42),
+            ..Default::default()
+        };
+        let manager = Manager::new();
+
+        assert_eq!(
+            Mcts::new(mcts_options.clone()).find_best_move(&manager),
+            Mcts::new(mcts_options).find_best_move(&manager)
+        );
+    }
 }
@@ -0,0 +1,172 @@
+use crate::ai::{Line, SearchOptions};
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use std::time::Duration;
+
+// makes the engine play like a weaker player, it searches every move properly and then
+// doesnt always pick the best one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Weakening {
+    pub score_noise: i32,    // added to every score, up to this much either way
+    pub temperature: f64,    // moves this much worse than the best are still picked now and then
+    pub blunder_chance: f64, // chance of a move picked completely at random
+}
+
+impl Weakening {
+    // index of the line to play, the lines have to be sorted best first
+    pub fn choose<R: Rng>(&self, lines: &[Line], rng: &mut R) -> usize {
+        if rng.gen_bool(self.blunder_chance) {
+            return rng.gen_range(0..lines.len());
+        }
+
+        let scores: Vec<f64> = lines
+            .iter()
+            .map(|x| (x.score + rng.gen_range(-self.score_noise..=self.score_noise)) as f64)
+            .collect();
+        let best = scores.iter().copied().fold(f64::MIN, f64::max);
+
+        if self.temperature <= 0f64 {
+            return scores.iter().position(|x| *x == best).unwrap();
+        }
+
+        // the chance drops off with how much the move loses
+        let weights = scores
+            .iter()
+            .map(|x| (-(best - x) / self.temperature).exp());
+
+        WeightedIndex::new(weights).unwrap().sample(rng)
+    }
+}
+
+#[derive(Copy, Clone, Eq, Debug, Default, PartialEq)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Medium,
+    #[default]
+    Hard, // the plain search
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Beginner,
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|x| x.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        let (depth, time, weakening) = match self {
+            Difficulty::Beginner => (
+                2,
+                None,
+                Some(Weakening {
+                    score_noise: 60,
+                    temperature: 80f64,
+                    blunder_chance: 0.15,
+                }),
+            ),
+            Difficulty::Easy => (
+                3,
+                None,
+                Some(Weakening {
+                    score_noise: 30,
+                    temperature: 40f64,
+                    blunder_chance: 0.05,
+                }),
+            ),
+            Difficulty::Medium => (
+                4,
+                Some(Duration::from_millis(500)),
+                Some(Weakening {
+                    score_noise: 10,
+                    temperature: 15f64,
+                    blunder_chance: 0.01,
+                }),
+            ),
+            Difficulty::Hard => (SearchOptions::default().depth, None, None),
+            Difficulty::Expert => (20, Some(Duration::from_secs(2)), None),
+        };
+
+        SearchOptions {
+            depth,
+            time,
+            weakening,
+            ..Default::default()
+        }
+    }
+
+    // the same with the evaluation of `evaluation`, tuned weights or a network
+    pub fn options_with(&self, evaluation: &SearchOptions) -> SearchOptions {
+        SearchOptions {
+            network: evaluation.network.clone(),
+            weights: evaluation.weights,
+            ..self.options()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Difficulty, Weakening};
+    use crate::ai::{Line, SearchOptions, Weights};
+    use rand::thread_rng;
+
+    #[test]
+    fn check_weakening() {
+        let lines: Vec<Line> = [50, 40, -300]
+            .into_iter()
+            .map(|score| Line {
+                score,
+                moves: Vec::new(),
+            })
+            .collect();
+        let mut rng = thread_rng();
+
+        let strong = Weakening {
+            score_noise: 0,
+            temperature: 0f64,
+            blunder_chance: 0f64,
+        };
+        assert_eq!(strong.choose(&lines, &mut rng), 0);
+
+        // the move losing 350 is as good as never picked without blunders
+        let weak = Weakening {
+            temperature: 20f64,
+            ..strong
+        };
+        let picks: Vec<usize> = (0..200).map(|_| weak.choose(&lines, &mut rng)).collect();
+        assert!(picks.contains(&1));
+        assert!(!picks.contains(&2));
+
+        assert_eq!(Difficulty::from_name("Easy"), Some(Difficulty::Easy));
+
+        // a level keeps the weights its given
+        let mut weights = Weights::default();
+        weights.0[0] += 1;
+        let evaluation = SearchOptions {
+            weights,
+            ..Default::default()
+        };
+        let options = Difficulty::Easy.options_with(&evaluation);
+        assert_eq!(options.weights, weights);
+        assert_eq!(options.depth, Difficulty::Easy.options().depth);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::ai::{self, SearchOptions, SearchResult};
+use crate::board::{Manager, Move, Player};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// a search running on its own thread so the caller can carry on meanwhile
+pub struct BackgroundSearch {
+    pub hash: u64, // of the position being searched
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Option<SearchResult>>,
+}
+
+impl BackgroundSearch {
+    pub fn start(manager: &Manager, options: SearchOptions) -> Self {
+        let mut manager = manager.clone();
+        manager.players = [Player::User, Player::User];
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+
+        BackgroundSearch {
+            hash: manager.hash(),
+            stop,
+            handle: thread::spawn(move || ai::search_until(&manager, &options, &flag)),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    // the search still gives back the last iteration it finished
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stop_after(&self, time: Duration) {
+        let stop = self.stop.clone();
+
+        thread::spawn(move || {
+            thread::sleep(time);
+            stop.store(true, Ordering::Relaxed);
+        });
+    }
+
+    // none if there was no move to search
+    pub fn wait(self) -> Option<SearchResult> {
+        self.handle.join().expect("the search thread panicked")
+    }
+}
+
+// thinks about the position after the reply the engine expects, while the opponent is
+// still deciding what to play
+pub struct Ponder {
+    pub predicted: Move,
+    search: BackgroundSearch,
+    time: Option<Duration>,
+}
+
+impl Ponder {
+    // `manager` has the opponent to move and `result` is the search for the move that got
+    // there, the line of the move played has the reply to expect. a weakened engine doesnt
+    // always play the first line, and none if no line has the move
+    pub fn start(
+        manager: &Manager,
+        result: &SearchResult,
+        options: &SearchOptions,
+    ) -> Option<Self> {
+        let line = result
+            .lines
+            .iter()
+            .find(|x| x.moves.first() == Some(&result.best_move))?;
+        let predicted = line.moves.get(1)?.clone();
+
+        let mut after = manager.clone();
+        after.players = [Player::User, Player::User];
+        after.play_move(predicted.clone());
+
+        if after.gameover {
+            return None;
+        }
+
+        // the clock only starts once the reply has actually been played
+        let search = BackgroundSearch::start(
+            &after,
+            SearchOptions {
+                time: None,
+                ..options.clone()
+            },
+        );
+
+        Some(Ponder {
+            predicted,
+            search,
+            time: options.time,
+        })
+    }
+
+    pub fn stop(self) {
+        self.search.stop();
+    }
+
+    // on a ponder hit the search carries on as the real one, otherwise its stopped and
+    // thrown away
+    pub fn resolve(self, manager: &Manager) -> Option<BackgroundSearch> {
+        if manager.hash() != self.search.hash {
+            self.search.stop();
+            return None;
+        }
+
+        if let Some(time) = self.time {
+            self.search.stop_after(time);
+        }
+
+        Some(self.search)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BackgroundSearch, Ponder};
+    use crate::ai::{self, SearchOptions};
+    use crate::board::Manager;
+
+    #[test]
+    fn check_ponder() {
+        let options = SearchOptions {
+            depth: 4,
+            ..Default::default()
+        };
+        let mut manager = Manager::new();
+
+        let result = BackgroundSearch::start(&manager, options.clone())
+            .wait()
+            .unwrap();
+        manager.play_move(result.best_move.clone());
+
+        // a miss is stopped and gives nothing back
+        let ponder = Ponder::start(&manager, &result, &options).unwrap();
+        let miss = manager
+            .legal_moves()
+            .into_iter()
+            .find(|x| *x != ponder.predicted)
+            .unwrap();
+        let mut missed = manager.clone();
+        missed.play_move(miss);
+        assert!(ponder.resolve(&missed).is_none());
+
+        // a hit carries on with the search of the position that came up
+        let ponder = Ponder::start(&manager, &result, &options).unwrap();
+        manager.play_move(ponder.predicted.clone());
+        let search = ponder.resolve(&manager).unwrap();
+        let reply = search.wait().unwrap();
+
+        assert!(manager.legal_moves().contains(&reply.best_move));
+        assert_eq!(reply.depth, ai::search(&manager, &options).unwrap().depth);
+
+        // a weakened engine can play its second line, the reply comes from that one
+        let manager = Manager::new();
+        let options = SearchOptions {
+            multi_pv: 2,
+            ..options
+        };
+        let mut result = ai::search(&manager, &options).unwrap();
+        result.best_move = result.lines[1].moves[0].clone();
+
+        let mut after = manager.clone();
+        after.play_move(result.best_move.clone());
+        let ponder = Ponder::start(&after, &result, &options).unwrap();
+        assert_eq!(ponder.predicted, result.lines[1].moves[1]);
+        ponder.stop();
+
+        // and without a line for the move played there is nothing to expect
+        result.lines.remove(1);
+        assert!(Ponder::start(&after, &result, &options).is_none());
+    }
+}
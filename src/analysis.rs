@@ -0,0 +1,289 @@
+use crate::ai::{self, SearchOptions};
+use crate::board::{Manager, Move, Piece};
+use crate::pdn::{self, Game};
+
+// replays a game through the engine and grades every move by how much of the side's
+// winning chances it threw away compared to the best move in the position
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq, PartialOrd, Ord)]
+pub enum Judgement {
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    // `loss` is the drop in winning chances, 0 to 1
+    fn from_loss(loss: f64) -> Self {
+        match loss {
+            x if x >= 0.12 => Judgement::Blunder,
+            x if x >= 0.06 => Judgement::Mistake,
+            x if x >= 0.03 => Judgement::Inaccuracy,
+            _ => Judgement::Good,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Judgement::Good => "good",
+            Judgement::Inaccuracy => "inaccuracy",
+            Judgement::Mistake => "mistake",
+            Judgement::Blunder => "blunder",
+        }
+    }
+
+    // the pdn move suffix
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Judgement::Good => "",
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+// the same curve the tuner fits scores to results with, a man up is about 64%
+fn win_chance(score: i32) -> f64 {
+    1f64 / (1f64 + 10f64.powf(-score as f64 / 400f64))
+}
+
+fn loss(best_score: i32, score: i32) -> f64 {
+    (win_chance(best_score) - win_chance(score)).max(0f64)
+}
+
+#[derive(Clone, Debug)]
+pub struct MoveAnalysis {
+    pub played: Move,
+    pub side: Piece,
+    pub best: Move,
+    pub best_score: i32, // both scores for the side that moved
+    pub score: i32,
+    pub judgement: Judgement,
+}
+
+impl MoveAnalysis {
+    pub fn loss(&self) -> f64 {
+        loss(self.best_score, self.score)
+    }
+
+    // 100 for the best move down to 0 for throwing away half the winning chances
+    pub fn accuracy(&self) -> f64 {
+        100f64 * (1f64 - 2f64 * self.loss()).max(0f64)
+    }
+}
+
+pub struct Analysis {
+    pub start: Manager, // the position before the first move
+    pub moves: Vec<MoveAnalysis>,
+    pub result: &'static str,
+}
+
+impl Analysis {
+    // the position before move `ply`, or after the last one
+    pub fn position(&self, ply: usize) -> Manager {
+        let mut manager = self.start.clone();
+
+        for analysed in self.moves.iter().take(ply) {
+            manager.play_move(analysed.played.clone());
+        }
+
+        manager
+    }
+
+    fn side_moves(&self, side: Piece) -> impl Iterator<Item = &MoveAnalysis> {
+        self.moves.iter().filter(move |x| x.side.match_piece(&side))
+    }
+
+    // mean accuracy of the sides moves
+    pub fn accuracy(&self, side: Piece) -> f64 {
+        let (total, count) = self.side_moves(side).fold((0f64, 0), |(total, count), x| {
+            (total + x.accuracy(), count + 1)
+        });
+
+        match count {
+            0 => 100f64,
+            _ => total / count as f64,
+        }
+    }
+
+    pub fn count(&self, side: Piece, judgement: Judgement) -> usize {
+        self.side_moves(side)
+            .filter(|x| x.judgement == judgement)
+            .count()
+    }
+
+    pub fn summary(&self) -> String {
+        [("blue", Piece::Blue(false)), ("red", Piece::Red(false))]
+            .iter()
+            .map(|(name, side)| {
+                format!(
+                    "{}: {:.1}% accuracy, {} inaccuracies, {} mistakes, {} blunders",
+                    name,
+                    self.accuracy(*side),
+                    self.count(*side, Judgement::Inaccuracy),
+                    self.count(*side, Judgement::Mistake),
+                    self.count(*side, Judgement::Blunder)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // every move that wasnt good gets its symbol and a comment with the better move
+    pub fn to_pdn(&self) -> String {
+        let variant = self.start.variant;
+        let mut tags = vec![
+            ("Event".to_owned(), "Analysis".to_owned()),
+            ("Variant".to_owned(), variant.name().to_owned()),
+        ];
+
+        if let Some(game_type) = pdn::game_type(variant) {
+            tags.push(("GameType".to_owned(), game_type.to_owned()));
+        }
+
+        // a game that didnt start from the usual setup cant be replayed without its position
+        let fen = pdn::to_fen(&self.start);
+        if fen != pdn::to_fen(&Manager::with_variant(variant)) {
+            tags.push(("FEN".to_owned(), fen));
+        }
+
+        tags.push(("Result".to_owned(), self.result.to_owned()));
+
+        for (name, side) in [("Blue", Piece::Blue(false)), ("Red", Piece::Red(false))] {
+            tags.push((
+                format!("{}Accuracy", name),
+                format!("{:.1}", self.accuracy(side)),
+            ));
+        }
+
+        let mut manager = self.start.clone();
+        let mut moves = Vec::new();
+        let mut annotations = Vec::new();
+
+        for analysed in &self.moves {
+            moves.push(pdn::move_to_string(&manager, &analysed.played));
+
+            annotations.push(match analysed.judgement {
+                Judgement::Good => String::new(),
+                judgement => format!(
+                    "{} {{{} {:+}, played {:+}}}",
+                    judgement.symbol(),
+                    pdn::move_to_string(&manager, &analysed.best),
+                    analysed.best_score,
+                    analysed.score
+                ),
+            });
+
+            manager.play_move(analysed.played.clone());
+        }
+
+        let game = Game {
+            tags,
+            moves,
+            result: self.result.to_owned(),
+        };
+
+        pdn::write_game(&game, &annotations)
+    }
+}
+
+// searches every position of the game `manager` has been through, `progress` gets the moves
+// done and the total
+pub fn analyse(
+    manager: &Manager,
+    options: &SearchOptions,
+    mut progress: impl FnMut(usize, usize),
+) -> Analysis {
+    let played = manager.played_moves();
+    let total = played.len();
+
+    let start = manager.initial();
+
+    // every move needs a proper score to compare it with the best one
+    let options = SearchOptions {
+        multi_pv: usize::MAX,
+        book: None,
+        weakening: None,
+        ..options.clone()
+    };
+
+    let mut position = start.clone();
+    let mut moves = Vec::new();
+
+    for (ply, played_move) in played.into_iter().enumerate() {
+        let result = match ai::search(&position, &options) {
+            Some(result) => result,
+            None => break,
+        };
+        let best = &result.lines[0];
+        let score = result
+            .lines
+            .iter()
+            .find(|x| x.moves[0] == played_move)
+            .map_or(best.score, |x| x.score);
+
+        moves.push(MoveAnalysis {
+            side: position.current_side(),
+            best: best.moves[0].clone(),
+            best_score: best.score,
+            score,
+            judgement: Judgement::from_loss(loss(best.score, score)),
+            played: played_move.clone(),
+        });
+
+        position.play_move(played_move);
+        progress(ply + 1, total);
+    }
+
+    Analysis {
+        start,
+        moves,
+        result: pdn::result(manager),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyse, Judgement};
+    use crate::ai::SearchOptions;
+    use crate::board::{Piece, Variant};
+    use crate::pdn::{self, read_games};
+
+    #[test]
+    fn check_analyse() {
+        // red gives a man away for nothing with 19-16
+        let game = &read_games("1. 11-15 23-19 2. 8-11 19-16")[0];
+        let manager = game.replay().unwrap();
+        let options = SearchOptions {
+            depth: 4,
+            ..Default::default()
+        };
+
+        let analysis = analyse(&manager, &options, |_, _| {});
+
+        assert_eq!(analysis.moves.len(), 4);
+        assert_eq!(analysis.moves[3].judgement, Judgement::Blunder);
+        assert_eq!(analysis.count(Piece::Red(false), Judgement::Blunder), 1);
+        assert!(analysis.accuracy(Piece::Red(false)) < analysis.accuracy(Piece::Blue(false)));
+
+        let text = analysis.to_pdn();
+        assert!(text.contains("19-16??"));
+        assert_eq!(read_games(&text)[0].moves, game.moves);
+        assert!(read_games(&text)[0].tag("FEN").is_none());
+
+        // from a set up position the game only replays with it
+        let fen = "B:B14,15:W22,23";
+        let mut manager = pdn::from_fen(Variant::Standard, fen).unwrap();
+        for text in ["14-18", "23x14", "15-19"] {
+            let played_move = pdn::parse_move(&manager, text).unwrap();
+            manager.play_move(played_move);
+        }
+
+        let analysis = analyse(&manager, &options, |_, _| {});
+        let game = &read_games(&analysis.to_pdn())[0];
+        assert_eq!(game.tag("FEN"), Some(fen));
+        assert_eq!(pdn::to_fen(&game.replay().unwrap()), pdn::to_fen(&manager));
+    }
+}
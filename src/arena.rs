@@ -0,0 +1,195 @@
+use crate::ai::{self, Difficulty, SearchOptions, Weights};
+use crate::ballot;
+use crate::board::{Manager, Piece, Player, Variant};
+use crate::cli::Args;
+use crate::nn::Network;
+use rand::Rng;
+use std::sync::Arc;
+
+// results from the point of view of the first engine
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Score {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn points(&self) -> f32 {
+        self.wins as f32 + self.draws as f32 / 2f32
+    }
+
+    // rating difference the score is worth, none while one side has every point
+    pub fn elo(&self) -> Option<f32> {
+        let share = self.points() / self.games() as f32;
+
+        match share > 0f32 && share < 1f32 {
+            true => Some(-400f32 * (1f32 / share - 1f32).log10()),
+            false => None,
+        }
+    }
+
+    pub fn add(&mut self, other: Score) {
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.draws += other.draws;
+    }
+
+    pub fn record(&mut self, winner: Piece, first_side: Piece) {
+        if winner.is_empty() {
+            self.draws += 1;
+        } else if winner.match_piece(&first_side) {
+            self.wins += 1;
+        } else {
+            self.losses += 1;
+        }
+    }
+}
+
+// plays the game out from `manager` and returns the winner, empty for a draw
+pub fn play_game(mut manager: Manager, blue: &SearchOptions, red: &SearchOptions) -> Piece {
+    manager.players = [Player::User, Player::User];
+
+    while !manager.gameover {
+        let options = match manager.current_side() {
+            Piece::Blue(_) => blue,
+            _ => red,
+        };
+
+        match ai::find_best_move_with(&manager, options) {
+            Some(best_move) => manager.play_move(best_move),
+            None => break,
+        }
+    }
+
+    manager.winner
+}
+
+// two games from the same ballot with the engines swapping colours
+pub fn play_pair(
+    variant: Variant,
+    ballot: &str,
+    first: &SearchOptions,
+    second: &SearchOptions,
+) -> Score {
+    let start = ballot::start_position(variant, ballot).expect("ballot isnt playable");
+    let mut score = Score::default();
+
+    // the ballot is always 3 moves so the first engine takes over as red
+    let winner = play_game(start.clone(), second, first);
+    score.record(winner, Piece::Red(false));
+
+    let winner = play_game(start, first, second);
+    score.record(winner, Piece::Blue(false));
+
+    score
+}
+
+// pairs from random ballots, `played` is told about each one as it finishes
+pub fn play_match<R: Rng>(
+    variant: Variant,
+    ballots: &[String],
+    pairs: usize,
+    (first, second): (&SearchOptions, &SearchOptions),
+    rng: &mut R,
+    mut played: impl FnMut(usize, &str, Score),
+) -> Score {
+    let mut score = Score::default();
+
+    for pair in 0..pairs {
+        let ballot = ballot::random_ballot(ballots, rng);
+        let pair_score = play_pair(variant, ballot, first, second);
+        played(pair, ballot, pair_score);
+        score.add(pair_score);
+    }
+
+    score
+}
+
+// settings for one engine from the command line, the options are all prefixed with its name,
+// `first` or `second`
+pub fn engine_options(args: &Args, name: &str, shared: &SearchOptions) -> SearchOptions {
+    let option = |option: &str| format!("{}-{}", name, option);
+
+    // a level sets the depth, time and weakening, anything else given still applies
+    let level = args.get_str(&option("level")).map(|x| {
+        Difficulty::from_name(x)
+            .unwrap_or_else(|| panic!("unknown level {}", x))
+            .options()
+    });
+    let base = level.as_ref().unwrap_or(shared);
+
+    SearchOptions {
+        depth: args.get(&option("depth"), base.depth),
+        time: base.time,
+        weakening: base.weakening,
+        network: args
+            .get_str(&option("network"))
+            .map(|path| Arc::new(Network::load(path).expect("couldnt read the network"))),
+        weights: match args.get_str(&option("weights")) {
+            Some(path) => Weights::load(path).expect("couldnt read the weights"),
+            None => Weights::default(),
+        },
+        quiescence: !args.flag(&option("no-quiescence")),
+        aspiration: !args.flag(&option("no-aspiration")),
+        pvs: !args.flag(&option("no-pvs")),
+        lmr: !args.flag(&option("no-lmr")),
+        ..shared.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{play_match, play_pair, Score};
+    use crate::ai::SearchOptions;
+    use crate::board::Variant;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn check_play_pair() {
+        let options = SearchOptions {
+            depth: 1,
+            ..Default::default()
+        };
+        let score = play_pair(Variant::Standard, "11-15 23-19 8-11", &options, &options);
+
+        assert_eq!(score.games(), 2);
+
+        let mut pairs = Vec::new();
+        let score = play_match(
+            Variant::Standard,
+            &["11-15 23-19 8-11".to_owned()],
+            2,
+            (&options, &options),
+            &mut StdRng::seed_from_u64(1),
+            |pair, _, score| pairs.push((pair, score.games())),
+        );
+        assert_eq!(score.games(), 4);
+        assert_eq!(pairs, vec![(0, 2), (1, 2)]);
+        assert_eq!(
+            Score {
+                wins: 1,
+                losses: 0,
+                draws: 1
+            }
+            .points(),
+            1.5
+        );
+
+        let even = Score {
+            wins: 3,
+            losses: 3,
+            draws: 4,
+        };
+        assert_eq!(even.elo(), Some(0f32));
+        assert_eq!(
+            Score { wins: 2, ..even }.elo().map(|x| x < 0f32),
+            Some(true)
+        );
+        assert_eq!(Score::default().elo(), None);
+    }
+}
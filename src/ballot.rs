@@ -0,0 +1,307 @@
+use crate::board::{Manager, Variant};
+use crate::pdn;
+use rand::Rng;
+use std::{fs, io};
+
+// the deck matches are played from, every position three moves can reach once, leaving out
+// the openings where a side drops a man to a 12 ply search. a tournament deck can be loaded
+// with `load_ballots` instead
+const DECK: [&str; 174] = [
+    "9-13 21-17 5-9",
+    "9-13 21-17 6-9",
+    "9-13 21-17 10-14",
+    "9-13 21-17 10-15",
+    "9-13 21-17 11-15",
+    "9-13 21-17 11-16",
+    "9-13 21-17 12-16",
+    "9-13 22-17 13x22",
+    "9-13 22-18 6-9",
+    "9-13 22-18 10-14",
+    "9-13 22-18 10-15",
+    "9-13 22-18 11-15",
+    "9-13 22-18 11-16",
+    "9-13 22-18 12-16",
+    "9-13 22-18 13-17",
+    "9-13 23-18 5-9",
+    "9-13 23-18 6-9",
+    "9-13 23-18 10-14",
+    "9-13 23-18 10-15",
+    "9-13 23-18 11-15",
+    "9-13 23-18 11-16",
+    "9-13 23-18 12-16",
+    "9-13 23-19 5-9",
+    "9-13 23-19 6-9",
+    "9-13 23-19 10-14",
+    "9-13 23-19 10-15",
+    "9-13 23-19 11-15",
+    "9-13 23-19 11-16",
+    "9-13 24-19 5-9",
+    "9-13 24-19 6-9",
+    "9-13 24-19 10-14",
+    "9-13 24-19 10-15",
+    "9-13 24-19 11-15",
+    "9-13 24-19 11-16",
+    "9-13 24-20 5-9",
+    "9-13 24-20 6-9",
+    "9-13 24-20 10-14",
+    "9-13 24-20 10-15",
+    "9-13 24-20 11-15",
+    "9-13 24-20 11-16",
+    "9-13 24-20 12-16",
+    "9-14 22-17 5-9",
+    "9-14 22-17 6-9",
+    "9-14 22-17 10-15",
+    "9-14 22-17 11-15",
+    "9-14 22-17 11-16",
+    "9-14 22-18 5-9",
+    "9-14 22-18 6-9",
+    "9-14 22-18 10-15",
+    "9-14 22-18 11-15",
+    "9-14 22-18 11-16",
+    "9-14 22-18 12-16",
+    "9-14 23-18 14x23",
+    "9-14 23-19 5-9",
+    "9-14 23-19 6-9",
+    "9-14 23-19 10-15",
+    "9-14 23-19 11-15",
+    "9-14 23-19 11-16",
+    "9-14 23-19 14-18",
+    "9-14 24-19 5-9",
+    "9-14 24-19 6-9",
+    "9-14 24-19 10-15",
+    "9-14 24-19 11-15",
+    "9-14 24-19 11-16",
+    "9-14 24-20 5-9",
+    "9-14 24-20 6-9",
+    "9-14 24-20 10-15",
+    "9-14 24-20 11-15",
+    "9-14 24-20 11-16",
+    "10-14 22-17 7-10",
+    "10-14 22-17 9-13",
+    "10-14 22-17 11-15",
+    "10-14 22-17 11-16",
+    "10-14 22-17 14-18",
+    "10-14 22-18 7-10",
+    "10-14 22-18 11-15",
+    "10-14 22-18 11-16",
+    "10-14 22-18 12-16",
+    "10-14 23-18 14x23",
+    "10-14 23-19 7-10",
+    "10-14 23-19 11-15",
+    "10-14 23-19 11-16",
+    "10-14 23-19 14-18",
+    "10-14 24-19 7-10",
+    "10-14 24-19 11-15",
+    "10-14 24-19 11-16",
+    "10-14 24-19 14-18",
+    "10-14 24-20 7-10",
+    "10-14 24-20 11-15",
+    "10-14 24-20 11-16",
+    "10-14 24-20 14-18",
+    "10-15 21-17 6-10",
+    "10-15 21-17 7-10",
+    "10-15 21-17 9-14",
+    "10-15 21-17 11-16",
+    "10-15 21-17 15-18",
+    "10-15 22-17 6-10",
+    "10-15 22-17 7-10",
+    "10-15 22-17 9-13",
+    "10-15 22-17 11-16",
+    "10-15 22-17 15-19",
+    "10-15 22-18 15x22",
+    "10-15 23-18 6-10",
+    "10-15 23-18 7-10",
+    "10-15 23-18 9-14",
+    "10-15 23-18 11-16",
+    "10-15 23-18 12-16",
+    "10-15 23-19 6-10",
+    "10-15 23-19 7-10",
+    "10-15 23-19 11-16",
+    "10-15 24-19 15x24",
+    "10-15 24-20 6-10",
+    "10-15 24-20 7-10",
+    "10-15 24-20 11-16",
+    "10-15 24-20 12-16",
+    "10-15 24-20 15-19",
+    "11-15 21-17 8-11",
+    "11-15 21-17 9-14",
+    "11-15 21-17 10-14",
+    "11-15 21-17 15-19",
+    "11-15 22-17 8-11",
+    "11-15 22-17 9-13",
+    "11-15 22-17 15-18",
+    "11-15 22-17 15-19",
+    "11-15 22-18 15x22",
+    "11-15 23-18 8-11",
+    "11-15 23-18 9-14",
+    "11-15 23-18 10-14",
+    "11-15 23-18 12-16",
+    "11-15 23-18 15-19",
+    "11-15 23-19 8-11",
+    "11-15 24-19 15x24",
+    "11-15 24-20 8-11",
+    "11-15 24-20 12-16",
+    "11-15 24-20 15-18",
+    "11-15 24-20 15-19",
+    "11-16 21-17 7-11",
+    "11-16 21-17 8-11",
+    "11-16 21-17 9-14",
+    "11-16 21-17 10-14",
+    "11-16 21-17 16-20",
+    "11-16 22-17 7-11",
+    "11-16 22-17 8-11",
+    "11-16 22-17 9-13",
+    "11-16 22-17 16-20",
+    "11-16 22-18 7-11",
+    "11-16 22-18 8-11",
+    "11-16 22-18 10-15",
+    "11-16 22-18 16-19",
+    "11-16 22-18 16-20",
+    "11-16 23-18 7-11",
+    "11-16 23-18 8-11",
+    "11-16 23-18 9-14",
+    "11-16 23-18 10-14",
+    "11-16 23-18 16-20",
+    "11-16 23-19 16x23",
+    "11-16 24-19 7-11",
+    "11-16 24-19 8-11",
+    "11-16 24-19 10-15",
+    "11-16 24-19 16-20",
+    "11-16 24-20 7-11",
+    "11-16 24-20 8-11",
+    "12-16 21-17 9-14",
+    "12-16 21-17 16-19",
+    "12-16 21-17 16-20",
+    "12-16 22-17 16-19",
+    "12-16 22-17 16-20",
+    "12-16 22-18 16-19",
+    "12-16 22-18 16-20",
+    "12-16 23-18 9-14",
+    "12-16 23-18 16-19",
+    "12-16 23-18 16-20",
+    "12-16 23-19 16x23",
+    "12-16 24-19 16-20",
+];
+
+pub fn ballots() -> Vec<String> {
+    DECK.iter().map(|x| x.to_string()).collect()
+}
+
+// every three move opening playable from the start, in square number order
+pub fn all_ballots() -> Vec<String> {
+    let mut manager = Manager::with_variant(Variant::Standard);
+    let mut ballots = Vec::new();
+
+    for first in manager.legal_moves() {
+        let first_text = pdn::move_to_string(&manager, &first);
+        manager.play_move(first);
+
+        for second in manager.legal_moves() {
+            let second_text = pdn::move_to_string(&manager, &second);
+            manager.play_move(second);
+
+            for third in manager.legal_moves() {
+                ballots.push(format!(
+                    "{} {} {}",
+                    first_text,
+                    second_text,
+                    pdn::move_to_string(&manager, &third)
+                ));
+            }
+
+            manager.undo_move();
+        }
+
+        manager.undo_move();
+    }
+
+    ballots.sort_by_key(|ballot| {
+        ballot
+            .split(|x: char| !x.is_ascii_digit())
+            .filter_map(|x| x.parse::<usize>().ok())
+            .collect::<Vec<usize>>()
+    });
+
+    ballots
+}
+
+// one ballot per line, anything after a # is ignored. a file without any is an error since
+// there would be nothing to draw from
+pub fn load_ballots(path: &str) -> io::Result<Vec<String>> {
+    let ballots: Vec<String> = fs::read_to_string(path)?
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim().to_owned())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    match ballots.is_empty() {
+        true => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no ballots in {}", path),
+        )),
+        false => Ok(ballots),
+    }
+}
+
+// plays the ballot out as real moves so the game history starts from the beginning
+pub fn start_position(variant: Variant, ballot: &str) -> Option<Manager> {
+    let mut manager = Manager::with_variant(variant);
+
+    for text in ballot.split_whitespace() {
+        let ballot_move = pdn::parse_move(&manager, text)?;
+        manager.play_move(ballot_move);
+    }
+
+    Some(manager)
+}
+
+pub fn random_ballot<'a, R: Rng>(ballots: &'a [String], rng: &mut R) -> &'a str {
+    &ballots[rng.gen_range(0..ballots.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all_ballots, ballots, load_ballots, start_position};
+    use crate::board::{Piece, Variant};
+    use crate::pdn;
+
+    #[test]
+    fn check_ballots() {
+        let all = all_ballots();
+
+        assert_eq!(all[0], "9-13 21-17 5-9");
+        assert!(all.windows(2).all(|x| x[0] != x[1]));
+
+        for ballot in all.iter() {
+            let manager = start_position(Variant::Standard, ballot).unwrap();
+            assert_eq!(manager.current_side(), Piece::Red(false));
+        }
+
+        assert!(start_position(Variant::Standard, "9-13 9-14 5-9").is_none());
+
+        // the deck is a part of them in the same order, with no two reaching the same position
+        let deck = ballots();
+        assert!(deck.len() < all.len());
+
+        let mut positions: Vec<String> = deck
+            .iter()
+            .map(|x| pdn::to_fen(&start_position(Variant::Standard, x).unwrap()))
+            .collect();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(positions.len(), deck.len());
+        assert!(deck.windows(2).all(|x| {
+            let position = |ballot| all.iter().position(|x| x == ballot).unwrap();
+            position(&x[0]) < position(&x[1])
+        }));
+
+        // a file with only comments has nothing to draw from
+        let path = std::env::temp_dir().join("checkers_ballots.txt");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "# 11-15 23-19 8-11\n\n").unwrap();
+        assert!(load_ballots(path).is_err());
+
+        std::fs::write(path, "11-15 23-19 8-11 # the old fourteenth\n").unwrap();
+        assert_eq!(load_ballots(path).unwrap(), vec!["11-15 23-19 8-11"]);
+    }
+}
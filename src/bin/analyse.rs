@@ -0,0 +1,112 @@
+use checkers_ai::ai::SearchOptions;
+use checkers_ai::analysis::{self, Judgement};
+use checkers_ai::board::Variant;
+use checkers_ai::cli::Args;
+use checkers_ai::egdb::EndgameDb;
+use checkers_ai::pdn;
+use checkers_ai::pns::{self, ProofOptions};
+use std::sync::Arc;
+
+// grades every move of the games in a pdn file and writes them back out annotated.
+// with --prove the positions around each mistake and blunder are also solved outright,
+// and --fen solves a single position instead of reading games
+//
+//   analyse games.pdn [--depth 8] [--threads 4] [--egdb endgame.db] [--out annotated.pdn]
+//                     [--prove 100000]
+//   analyse --fen B:B14:W18 [--variant standard] [--prove 100000] [--egdb endgame.db]
+fn main() {
+    let args = Args::from_env();
+    let egdb = args
+        .get_str("egdb")
+        .map(|path| Arc::new(EndgameDb::load(path).expect("couldnt read the endgame database")));
+    let proof = ProofOptions {
+        max_nodes: args.get("prove", 0),
+        egdb: egdb.clone(),
+    };
+
+    if let Some(fen) = args.get_str("fen") {
+        let name = args.get_str("variant").unwrap_or("standard");
+        let variant =
+            Variant::from_name(name).unwrap_or_else(|| panic!("unknown variant {}", name));
+        let manager = pdn::from_fen(variant, fen).expect("couldnt read the FEN");
+
+        let result = pns::prove(
+            &manager,
+            &ProofOptions {
+                max_nodes: args.get("prove", 1_000_000),
+                ..proof
+            },
+        );
+
+        println!("{} after {} nodes", result.outcome.name(), result.nodes);
+
+        if !result.line.is_empty() {
+            println!("{}", pdn::line_to_string(&manager, &result.line));
+        }
+        return;
+    }
+
+    let path = args
+        .positional
+        .first()
+        .expect("usage: analyse games.pdn [--depth 8] [--out annotated.pdn]");
+
+    let text = std::fs::read_to_string(path).expect("couldnt read the pdn file");
+    let games = pdn::read_games(&text);
+
+    let options = SearchOptions {
+        depth: args.get("depth", SearchOptions::default().depth),
+        threads: args.get("threads", 1),
+        egdb,
+        ..Default::default()
+    };
+
+    let mut annotated = Vec::new();
+
+    for (number, game) in games.iter().enumerate() {
+        let manager = match game.replay() {
+            Some(manager) => manager,
+            None => {
+                eprintln!("game {}: skipped, it has an illegal move", number + 1);
+                continue;
+            }
+        };
+
+        let analysis = analysis::analyse(&manager, &options, |done, total| {
+            eprint!("\rgame {}: {}/{} moves", number + 1, done, total);
+        });
+        eprintln!();
+
+        println!("game {}", number + 1);
+        println!("{}", analysis.summary());
+
+        // both outcomes for the side that moved
+        if proof.max_nodes > 0 {
+            for (ply, analysed) in analysis.moves.iter().enumerate() {
+                if analysed.judgement < Judgement::Mistake {
+                    continue;
+                }
+
+                let before = analysis.position(ply);
+                let after = analysis.position(ply + 1);
+
+                println!(
+                    "  {}{}: {} before, {} after",
+                    pdn::move_to_string(&before, &analysed.played),
+                    analysed.judgement.symbol(),
+                    pns::prove(&before, &proof).outcome.name(),
+                    pns::prove(&after, &proof).outcome.flipped().name()
+                );
+            }
+        }
+
+        annotated.push(analysis.to_pdn());
+    }
+
+    match args.get_str("out") {
+        Some(out) => {
+            std::fs::write(out, annotated.join("\n")).expect("couldnt write the annotated games")
+        }
+        None => println!("\n{}", annotated.join("\n")),
+    }
+}
@@ -0,0 +1,84 @@
+use checkers_ai::ai::SearchOptions;
+use checkers_ai::arena;
+use checkers_ai::ballot;
+use checkers_ai::board::Variant;
+use checkers_ai::book::{Book, BookSelection};
+use checkers_ai::cli::Args;
+use checkers_ai::egdb::EndgameDb;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::sync::Arc;
+
+// plays engine settings against each other, every ballot twice with the colours reversed
+//
+//   arena --pairs 50 --first-depth 6 --second-depth 4 [--ballots deck.txt] [--ballot "11-15 23-19 8-11"]
+//         [--egdb endgame.db] [--book opening.book [--book-weighted]] [--threads 4] [--seed 1234]
+//
+// and for either engine [--first-network first.nn] [--first-weights first.txt] [--first-no-quiescence]
+// [--first-no-aspiration] [--first-no-pvs] [--first-no-lmr]
+// [--first-level beginner|easy|medium|hard|expert]
+fn main() {
+    let args = Args::from_env();
+
+    // the same seed plays the same match again, as long as there are no time limits or helpers
+    let seed = args.get("seed", thread_rng().gen::<u64>());
+    println!("seed {}", seed);
+
+    let shared = SearchOptions {
+        egdb: args.get_str("egdb").map(|path| {
+            Arc::new(EndgameDb::load(path).expect("couldnt read the endgame database"))
+        }),
+        book: args
+            .get_str("book")
+            .map(|path| Arc::new(Book::load(path).expect("couldnt read the opening book"))),
+        book_selection: match args.flag("book-weighted") {
+            true => BookSelection::Weighted,
+            false => BookSelection::Best,
+        },
+        threads: args.get("threads", 1),
+        seed: Some(seed),
+        ..Default::default()
+    };
+
+    let first = arena::engine_options(&args, "first", &shared);
+    let second = arena::engine_options(&args, "second", &shared);
+
+    let ballots = match (args.get_str("ballot"), args.get_str("ballots")) {
+        (Some(ballot), _) => vec![ballot.to_owned()],
+        (None, Some(path)) => ballot::load_ballots(path).expect("couldnt read the ballots"),
+        (None, None) => ballot::ballots(),
+    };
+
+    let pairs: usize = args.get("pairs", 10);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let total = arena::play_match(
+        Variant::Standard,
+        &ballots,
+        pairs,
+        (&first, &second),
+        &mut rng,
+        |pair, ballot, score| {
+            println!(
+                "pair {:>3} [{}] +{} -{} ={}",
+                pair + 1,
+                ballot,
+                score.wins,
+                score.losses,
+                score.draws
+            )
+        },
+    );
+
+    println!(
+        "first engine: +{} -{} ={} ({:.1}/{})",
+        total.wins,
+        total.losses,
+        total.draws,
+        total.points(),
+        total.games()
+    );
+
+    if let Some(elo) = total.elo() {
+        println!("elo difference {:+.0}", elo);
+    }
+}
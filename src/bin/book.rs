@@ -0,0 +1,46 @@
+use checkers_ai::ai::SearchOptions;
+use checkers_ai::book::Book;
+use checkers_ai::cli::Args;
+use checkers_ai::pdn;
+use std::time::Instant;
+
+// builds an opening book for standard checkers, either out of a pdn file of games
+// or by letting the engine pick its own move in every position near the start
+//
+//   book --pdn games.pdn --plies 16 --out opening.book
+//   book --search-depth 8 --plies 4 --out opening.book
+fn main() {
+    let args = Args::from_env();
+    let out = args.get_str("out").unwrap_or("opening.book").to_owned();
+
+    let book = match args.get_str("pdn") {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).expect("couldnt read the pdn file");
+            let games = pdn::read_games(&text);
+            println!("read {} games", games.len());
+
+            Book::from_games(&games, args.get("plies", 16))
+        }
+        None => {
+            let options = SearchOptions {
+                depth: args.get("search-depth", SearchOptions::default().depth),
+                ..Default::default()
+            };
+            let start = Instant::now();
+
+            Book::from_search(&options, args.get("plies", 4), |positions| {
+                if positions % 100 == 0 {
+                    println!(
+                        "{:>8.1}s  {} positions",
+                        start.elapsed().as_secs_f32(),
+                        positions
+                    );
+                }
+            })
+        }
+    };
+
+    book.save(&out).expect("couldnt write the book");
+
+    println!("wrote {} positions to {}", book.len(), out);
+}
@@ -0,0 +1,32 @@
+use checkers_ai::cli::Args;
+use checkers_ai::egdb::EndgameDb;
+use std::time::Instant;
+
+// builds the endgame databases for standard checkers
+//
+//   egdb --pieces 4 --out endgame.db [--wdl]
+//
+// --wdl only keeps win/loss/draw, otherwise the distance to the end is stored as well
+fn main() {
+    let args = Args::from_env();
+    let pieces: usize = args.get("pieces", 4);
+    let out = args.get_str("out").unwrap_or("endgame.db").to_owned();
+
+    let start = Instant::now();
+    let db = EndgameDb::generate(pieces, |material, positions| {
+        println!(
+            "{:>8.1}s  blue {}m {}k  red {}m {}k  {} positions",
+            start.elapsed().as_secs_f32(),
+            material.blue_men,
+            material.blue_kings,
+            material.red_men,
+            material.red_kings,
+            positions
+        );
+    });
+
+    db.save(&out, !args.flag("wdl"))
+        .expect("couldnt write the database");
+
+    println!("wrote {} pieces to {}", pieces, out);
+}
@@ -0,0 +1,323 @@
+use checkers_ai::ai::{
+    self, BackgroundSearch, Difficulty, Iteration, SearchEvent, SearchOptions, SearchResult,
+    Weights,
+};
+use checkers_ai::board::{Manager, Variant};
+use checkers_ai::cli::Args;
+use checkers_ai::nn::Network;
+use checkers_ai::pdn;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// line based protocol so other programs can drive the engine, loosely after uci.
+// moves are in pdn notation, a search answers with an `info` line per iteration and then
+// `bestmove`. it starts on the default evaluation unless given one
+//
+//   engine [--weights tuned.txt] [--network standard.nn]
+//
+//   variant standard|giveaway|turkish|international|canadian
+//   huffing on|off
+//   new
+//   position startpos [moves 11-15 23-19 ...]
+//   weights FILE|default   the evaluation weights every search after it uses
+//   network FILE|off       the network evaluating standard checkers instead
+//   go [depth N] [movetime MS] [level NAME] [ponder] [tree FILE]
+//   ponderhit      the move pondered on was played, the search goes on as the real one
+//   stop           answers with the best move found so far
+//   eval           the static evaluation of the position term by term
+//   isready        answered with readyok
+//   quit
+
+struct Job {
+    search: BackgroundSearch,
+    manager: Manager,
+    pondering: bool, // the bestmove is held back until ponderhit or stop
+    movetime: Option<Duration>,
+    events: Receiver<SearchEvent>,
+}
+
+impl Job {
+    fn report_iterations(&self) {
+        for event in self.events.try_iter() {
+            if let SearchEvent::Iteration(iteration) = event {
+                info(&self.manager, &iteration);
+            }
+        }
+    }
+}
+
+fn info(manager: &Manager, iteration: &Iteration) {
+    println!(
+        "info depth {} seldepth {} score {} nodes {} nps {} tthits {:.0} ebf {:.2} pv {}",
+        iteration.depth,
+        iteration.stats.seldepth,
+        iteration.score,
+        iteration.stats.nodes,
+        iteration.nodes_per_second(),
+        iteration.stats.tt_hit_rate() * 100f64,
+        iteration.branching_factor,
+        pdn::line_to_string(manager, &iteration.pv)
+    );
+}
+
+fn report(manager: &Manager, result: &SearchResult) {
+    let pv = result
+        .lines
+        .first()
+        .map(|x| x.moves.as_slice())
+        .unwrap_or(&[]);
+
+    let mut after = manager.clone();
+    after.play_move(result.best_move.clone());
+
+    match pv.get(1) {
+        Some(reply) if pv[0] == result.best_move => println!(
+            "bestmove {} ponder {}",
+            pdn::move_to_string(manager, &result.best_move),
+            pdn::move_to_string(&after, reply)
+        ),
+        _ => println!(
+            "bestmove {}",
+            pdn::move_to_string(manager, &result.best_move)
+        ),
+    }
+}
+
+fn set_position(manager: &mut Manager, words: &[&str]) -> Result<(), String> {
+    let mut position = Manager::with_variant(manager.variant);
+    position.huffing = manager.huffing;
+
+    let mut words = words.iter();
+
+    match words.next() {
+        Some(&"startpos") => {}
+        _ => return Err("only startpos positions are supported".to_owned()),
+    }
+
+    if let Some(word) = words.next() {
+        if *word != "moves" {
+            return Err(format!("unexpected {}", word));
+        }
+    }
+
+    for text in words {
+        match pdn::parse_move(&position, text) {
+            Some(played_move) if !position.gameover => position.play_move(played_move),
+            _ => return Err(format!("illegal move {}", text)),
+        }
+    }
+
+    *manager = position;
+    Ok(())
+}
+
+// the weights and network in `evaluation` are kept whatever level is asked for
+fn go(manager: &Manager, words: &[&str], evaluation: &SearchOptions) -> Result<Job, String> {
+    let mut options = SearchOptions {
+        network: evaluation.network.clone(),
+        weights: evaluation.weights,
+        ..Default::default()
+    };
+    let mut depth = None;
+    let mut movetime = None;
+    let mut pondering = false;
+    let mut words = words.iter();
+
+    while let Some(word) = words.next() {
+        let mut value = || {
+            words
+                .next()
+                .ok_or_else(|| format!("{} needs a value", word))
+        };
+
+        match *word {
+            "depth" => depth = Some(value()?.parse().map_err(|_| "invalid depth")?),
+            "movetime" => {
+                let ms = value()?.parse().map_err(|_| "invalid movetime")?;
+                movetime = Some(Duration::from_millis(ms));
+            }
+            "level" => {
+                let name = value()?;
+                options = Difficulty::from_name(name)
+                    .ok_or_else(|| format!("unknown level {}", name))?
+                    .options_with(evaluation);
+            }
+            "ponder" => pondering = true,
+            "tree" => options.tree_dump = Some(value()?.to_string()),
+            _ => return Err(format!("unexpected {}", word)),
+        }
+    }
+
+    if manager.gameover {
+        return Err("the game is over".to_owned());
+    }
+
+    // with only a time given the search goes as deep as the clock allows
+    options.depth = match (depth, movetime) {
+        (Some(depth), _) => depth,
+        (None, Some(_)) => 64,
+        (None, None) => options.depth,
+    };
+
+    if movetime.is_some() {
+        options.time = movetime;
+    }
+
+    // the clock only runs once the ponder hit comes in
+    if pondering {
+        options.time = None;
+    }
+
+    let (sender, events) = mpsc::channel();
+    options.events = Some(sender);
+
+    Ok(Job {
+        search: BackgroundSearch::start(manager, options),
+        manager: manager.clone(),
+        pondering,
+        movetime,
+        events,
+    })
+}
+
+fn main() {
+    let (sender, commands) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let args = Args::from_env();
+    let mut evaluation = SearchOptions {
+        network: args
+            .get_str("network")
+            .map(|path| Arc::new(Network::load(path).expect("couldnt read the network"))),
+        weights: match args.get_str("weights") {
+            Some(path) => Weights::load(path).expect("couldnt read the weights"),
+            None => Weights::default(),
+        },
+        ..Default::default()
+    };
+
+    let mut manager = Manager::new();
+    let mut job: Option<Job> = None;
+
+    loop {
+        if let Some(job) = &job {
+            job.report_iterations();
+        }
+
+        if job
+            .as_ref()
+            .is_some_and(|x| !x.pondering && x.search.is_finished())
+        {
+            let job = job.take().unwrap();
+            job.report_iterations();
+            match job.search.wait() {
+                Some(result) => report(&job.manager, &result),
+                None => println!("bestmove none"),
+            }
+        }
+
+        let line = match commands.recv_timeout(Duration::from_millis(5)) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => "quit".to_owned(),
+        };
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let outcome = match words.first() {
+            None => Ok(()),
+            Some(&"isready") => {
+                println!("readyok");
+                Ok(())
+            }
+            Some(&"variant") => match words.get(1).and_then(|x| Variant::from_name(x)) {
+                Some(variant) => {
+                    let huffing = manager.huffing;
+                    manager = Manager::with_variant(variant);
+                    manager.huffing = huffing;
+                    Ok(())
+                }
+                None => Err("unknown variant".to_owned()),
+            },
+            Some(&"huffing") => {
+                manager.huffing = words.get(1) == Some(&"on");
+                Ok(())
+            }
+            Some(&"new") => {
+                let huffing = manager.huffing;
+                manager = Manager::with_variant(manager.variant);
+                manager.huffing = huffing;
+                Ok(())
+            }
+            Some(&"position") => set_position(&mut manager, &words[1..]),
+            Some(&"go") => match job {
+                Some(_) => Err("already searching".to_owned()),
+                None => go(&manager, &words[1..], &evaluation).map(|x| job = Some(x)),
+            },
+            Some(&"weights") => match words.get(1) {
+                Some(&"default") => {
+                    evaluation.weights = Weights::default();
+                    Ok(())
+                }
+                Some(path) => Weights::load(path)
+                    .map(|x| evaluation.weights = x)
+                    .map_err(|x| format!("couldnt read the weights: {}", x)),
+                None => Err("weights needs a file".to_owned()),
+            },
+            Some(&"network") => match words.get(1) {
+                Some(&"off") => {
+                    evaluation.network = None;
+                    Ok(())
+                }
+                Some(path) => Network::load(path)
+                    .map(|x| evaluation.network = Some(Arc::new(x)))
+                    .map_err(|x| format!("couldnt read the network: {}", x)),
+                None => Err("network needs a file".to_owned()),
+            },
+            Some(&"ponderhit") => match job.as_mut() {
+                Some(job) if job.pondering => {
+                    job.pondering = false;
+
+                    if let Some(time) = job.movetime {
+                        job.search.stop_after(time);
+                    }
+                    Ok(())
+                }
+                _ => Err("not pondering".to_owned()),
+            },
+            Some(&"stop") => {
+                if let Some(job) = job.as_mut() {
+                    job.pondering = false;
+                    job.search.stop();
+                }
+                Ok(())
+            }
+            Some(&"eval") => {
+                for line in ai::evaluate_explained_with(&manager, &evaluation.weights).lines() {
+                    println!("{}", line);
+                }
+                Ok(())
+            }
+            Some(&"quit") => break,
+            Some(word) => Err(format!("unknown command {}", word)),
+        };
+
+        if let Err(message) = outcome {
+            println!("info string {}", message);
+        }
+    }
+
+    if let Some(job) = job {
+        job.search.stop();
+    }
+}
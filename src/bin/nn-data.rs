@@ -0,0 +1,38 @@
+use checkers_ai::ai::SearchOptions;
+use checkers_ai::cli::Args;
+use checkers_ai::nn::{self, Network};
+use rand::thread_rng;
+use std::sync::Arc;
+
+// plays the engine against itself and writes every position with the result of its game
+//
+//   nn-data --games 200 --depth 4 --random-plies 6 --out selfplay.data [--network current.nn]
+fn main() {
+    let args = Args::from_env();
+    let out = args.get_str("out").unwrap_or("selfplay.data").to_owned();
+
+    let options = SearchOptions {
+        depth: args.get("depth", 4),
+        network: args
+            .get_str("network")
+            .map(|path| Arc::new(Network::load(path).expect("couldnt read the network"))),
+        ..Default::default()
+    };
+
+    let games: usize = args.get("games", 100);
+    let random_plies = args.get("random-plies", 6);
+    let mut rng = thread_rng();
+    let mut samples = Vec::new();
+
+    for game in 0..games {
+        samples.extend(nn::self_play_game(&options, random_plies, &mut rng));
+
+        if (game + 1) % 10 == 0 {
+            println!("{} games, {} positions", game + 1, samples.len());
+        }
+    }
+
+    nn::save_samples(&out, &samples).expect("couldnt write the samples");
+
+    println!("wrote {} positions to {}", samples.len(), out);
+}
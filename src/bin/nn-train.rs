@@ -0,0 +1,39 @@
+use checkers_ai::cli::Args;
+use checkers_ai::nn::{self, Network, Trainer, DEFAULT_HIDDEN};
+use rand::thread_rng;
+
+// trains the evaluation network on self-play data, positional arguments are the data files
+//
+//   nn-train selfplay.data more.data --epochs 20 --rate 0.001 --batch 256 --out current.nn
+//            [--hidden 64] [--init previous.nn]
+fn main() {
+    let args = Args::from_env();
+    let out = args.get_str("out").unwrap_or("current.nn").to_owned();
+    let mut rng = thread_rng();
+
+    let mut samples = Vec::new();
+    for path in &args.positional {
+        samples.extend(nn::load_samples(path).expect("couldnt read the samples"));
+    }
+    println!("{} positions", samples.len());
+
+    let network = match args.get_str("init") {
+        Some(path) => Network::load(path).expect("couldnt read the network"),
+        None => Network::new(args.get("hidden", DEFAULT_HIDDEN), &mut rng),
+    };
+
+    let mut trainer = Trainer::new(network, args.get("rate", 0.001));
+    let batch = args.get("batch", 256);
+
+    for epoch in 0..args.get("epochs", 20) {
+        let loss = trainer.epoch(&mut samples, batch, &mut rng);
+        println!("epoch {:>3}  loss {:.4}", epoch + 1, loss);
+    }
+
+    trainer
+        .network
+        .save(&out)
+        .expect("couldnt write the network");
+
+    println!("wrote {}", out);
+}
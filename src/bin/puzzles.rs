@@ -0,0 +1,80 @@
+use checkers_ai::ai::{self, Difficulty, SearchOptions};
+use checkers_ai::ballot;
+use checkers_ai::board::{Manager, Variant};
+use checkers_ai::cli::Args;
+use checkers_ai::pdn;
+use checkers_ai::puzzle::{self, PuzzleOptions};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+// looks for tactics in games and writes them out as a puzzle file, the games either come
+// from a pdn file or are played here by two weakened engines that leave shots on the board
+//
+//   puzzles --pdn games.pdn [--out puzzles.pdn]
+//   puzzles --selfplay 20 [--level easy] [--seed 1234]
+//
+// and for both [--scan-depth 6] [--verify-depth 10] [--margin 150] [--max-plies 9]
+// [--proof-nodes 20000]
+fn main() {
+    let args = Args::from_env();
+    let defaults = PuzzleOptions::default();
+    let options = PuzzleOptions {
+        scan_depth: args.get("scan-depth", defaults.scan_depth),
+        verify_depth: args.get("verify-depth", defaults.verify_depth),
+        margin: args.get("margin", defaults.margin),
+        max_plies: args.get("max-plies", defaults.max_plies),
+        proof_nodes: args.get("proof-nodes", defaults.proof_nodes),
+    };
+
+    let games: Box<dyn Iterator<Item = Manager>> = match args.get_str("pdn") {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).expect("couldnt read the pdn file");
+            Box::new(
+                pdn::read_games(&text)
+                    .into_iter()
+                    .filter_map(|x| x.replay()),
+            )
+        }
+        None => {
+            let level = args.get_str("level").unwrap_or("easy");
+            let seed = args.get("seed", thread_rng().gen::<u64>());
+            println!("seed {}", seed);
+
+            let search = SearchOptions {
+                seed: Some(seed),
+                ..Difficulty::from_name(level)
+                    .unwrap_or_else(|| panic!("unknown level {}", level))
+                    .options()
+            };
+            let ballots = ballot::ballots();
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            Box::new((0..args.get("selfplay", 10)).map(move |_| {
+                let ballot = ballot::random_ballot(&ballots, &mut rng);
+                let mut manager = ballot::start_position(Variant::Standard, ballot).unwrap();
+
+                while !manager.gameover {
+                    match ai::find_best_move_with(&manager, &search) {
+                        Some(best_move) => manager.play_move(best_move),
+                        None => break,
+                    }
+                }
+
+                manager
+            }))
+        }
+    };
+
+    let mut puzzles = Vec::new();
+
+    for (number, game) in games.enumerate() {
+        let found = puzzle::find_puzzles(&game, &options);
+        println!("game {}: {} puzzles", number + 1, found.len());
+
+        puzzles.extend(found);
+    }
+
+    let out = args.get_str("out").unwrap_or("puzzles.pdn");
+    std::fs::write(out, puzzle::write_puzzles(&puzzles)).expect("couldnt write the puzzles");
+
+    println!("wrote {} puzzles to {}", puzzles.len(), out);
+}
@@ -0,0 +1,227 @@
+use checkers_ai::ai::{self, SearchOptions};
+use checkers_ai::arena::{self, Score};
+use checkers_ai::ballot;
+use checkers_ai::board::{Piece, Player, Variant};
+use checkers_ai::cli::Args;
+use checkers_ai::egdb::EndgameDb;
+use checkers_ai::nn::{self, Sample, ScoredSample};
+use checkers_ai::pdn::{self, Game};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+struct Settings {
+    first: SearchOptions,
+    second: SearchOptions,
+    ballots: Vec<String>,
+    random_plies: usize,
+    seed: u64,
+}
+
+struct Played {
+    number: usize,
+    game: Game,
+    samples: Vec<ScoredSample>,
+    score: Score, // for the first engine
+}
+
+// game `number` always comes out the same for a seed, the first engine is blue in the even ones
+fn play(settings: &Settings, number: usize) -> Played {
+    let seed = settings.seed.wrapping_add(number as u64);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let ballot = ballot::random_ballot(&settings.ballots, &mut rng);
+    let mut manager =
+        ballot::start_position(Variant::Standard, ballot).expect("ballot isnt playable");
+    manager.players = [Player::User, Player::User];
+
+    for _ in 0..settings.random_plies {
+        if manager.gameover {
+            break;
+        }
+
+        let random_move = manager.legal_moves().choose(&mut rng).unwrap().clone();
+        manager.play_move(random_move);
+    }
+
+    let engines = [&settings.first, &settings.second].map(|x| SearchOptions {
+        seed: Some(seed),
+        ..x.clone()
+    });
+    let (blue, red) = match number.is_multiple_of(2) {
+        true => (&engines[0], &engines[1]),
+        false => (&engines[1], &engines[0]),
+    };
+
+    let mut positions = Vec::new();
+
+    while !manager.gameover {
+        let side = manager.current_side();
+        let options = match side {
+            Piece::Blue(_) => blue,
+            _ => red,
+        };
+
+        let result = match ai::search(&manager, options) {
+            Some(result) => result,
+            None => break,
+        };
+        positions.push((nn::position(&manager), side, result.score));
+        manager.play_move(result.best_move);
+    }
+
+    let outcome = |side: Piece| match manager.winner {
+        Piece::Empty => 0,
+        winner if winner.match_piece(&side) => 1,
+        _ => -1,
+    };
+
+    let samples = positions
+        .into_iter()
+        .map(|(position, side, score)| ScoredSample {
+            sample: Sample {
+                position,
+                result: outcome(side),
+            },
+            score: score.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+        })
+        .collect();
+
+    let first_side = match number.is_multiple_of(2) {
+        true => Piece::Blue(false),
+        false => Piece::Red(false),
+    };
+    let mut score = Score::default();
+    score.record(manager.winner, first_side);
+
+    let names = match number.is_multiple_of(2) {
+        true => ["first", "second"],
+        false => ["second", "first"],
+    };
+    let result = pdn::result(&manager);
+
+    let mut position = manager.initial();
+    let moves = manager
+        .played_moves()
+        .into_iter()
+        .map(|x| {
+            let text = pdn::move_to_string(&position, &x);
+            position.play_move(x);
+            text
+        })
+        .collect();
+
+    let game = Game {
+        tags: vec![
+            ("Event".to_owned(), "Selfplay".to_owned()),
+            ("Round".to_owned(), (number + 1).to_string()),
+            ("Black".to_owned(), names[0].to_owned()),
+            ("White".to_owned(), names[1].to_owned()),
+            ("GameType".to_owned(), "21".to_owned()),
+            ("Ballot".to_owned(), ballot.to_owned()),
+            ("Result".to_owned(), result.to_owned()),
+        ],
+        moves,
+        result: result.to_owned(),
+    };
+
+    Played {
+        number,
+        game,
+        samples,
+        score,
+    }
+}
+
+// plays engines against each other on every core to make training and test data. each game
+// starts from a random ballot and a few random moves, and the engines swap colours every game
+//
+//   selfplay --games 1000 [--out selfplay.pdn] [--data selfplay.data] [--workers 8]
+//            [--random-plies 4] [--ballots deck.txt] [--egdb endgame.db] [--seed 1234]
+//
+// and for either engine the same options as arena, [--first-depth 6] [--second-level easy] ...
+fn main() {
+    let args = Args::from_env();
+
+    let seed = args.get("seed", thread_rng().gen::<u64>());
+    println!("seed {}", seed);
+
+    let shared = SearchOptions {
+        egdb: args.get_str("egdb").map(|path| {
+            Arc::new(EndgameDb::load(path).expect("couldnt read the endgame database"))
+        }),
+        ..Default::default()
+    };
+
+    let settings = Arc::new(Settings {
+        first: arena::engine_options(&args, "first", &shared),
+        second: arena::engine_options(&args, "second", &shared),
+        ballots: match args.get_str("ballots") {
+            Some(path) => ballot::load_ballots(path).expect("couldnt read the ballots"),
+            None => ballot::ballots(),
+        },
+        random_plies: args.get("random-plies", 4),
+        seed,
+    });
+
+    let games: usize = args.get("games", 100);
+    let workers = args.get(
+        "workers",
+        thread::available_parallelism().map_or(1, |x| x.get()),
+    );
+
+    // the workers take the next game number until there are none left
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, played) = mpsc::channel();
+
+    for _ in 0..workers.clamp(1, games.max(1)) {
+        let settings = settings.clone();
+        let next = next.clone();
+        let sender = sender.clone();
+
+        thread::spawn(move || loop {
+            let number = next.fetch_add(1, Ordering::SeqCst);
+
+            if number >= games || sender.send(play(&settings, number)).is_err() {
+                break;
+            }
+        });
+    }
+    drop(sender);
+
+    let mut results = Vec::new();
+    let mut total = Score::default();
+
+    for game in played {
+        total.add(game.score);
+        results.push(game);
+
+        if results.len().is_multiple_of(10) || results.len() == games {
+            println!(
+                "{} games, first engine +{} -{} ={}",
+                results.len(),
+                total.wins,
+                total.losses,
+                total.draws
+            );
+        }
+    }
+
+    results.sort_by_key(|x| x.number);
+
+    let out = args.get_str("out").unwrap_or("selfplay.pdn");
+    let text = results
+        .iter()
+        .map(|x| pdn::write_game(&x.game, &[]))
+        .collect::<Vec<String>>()
+        .join("\n");
+    std::fs::write(out, text).expect("couldnt write the games");
+    println!("wrote {} games to {}", results.len(), out);
+
+    if let Some(data) = args.get_str("data") {
+        let samples: Vec<ScoredSample> = results.into_iter().flat_map(|x| x.samples).collect();
+        nn::save_scored_samples(data, &samples).expect("couldnt write the samples");
+        println!("wrote {} positions to {}", samples.len(), data);
+    }
+}
@@ -0,0 +1,55 @@
+use checkers_ai::ai::{Weights, TERM_NAMES};
+use checkers_ai::cli::Args;
+use checkers_ai::{nn, pdn, tune};
+
+// fits the evaluation weights to game results, positional arguments are pdn files or
+// self-play data files from nn-data
+//
+//   tune games.pdn selfplay.data --out weights.txt [--init weights.txt] [--passes 100] [--skip 8]
+fn main() {
+    let args = Args::from_env();
+    let out = args.get_str("out").unwrap_or("weights.txt").to_owned();
+
+    let mut positions = Vec::new();
+    for path in &args.positional {
+        match path.ends_with(".pdn") {
+            true => {
+                let text = std::fs::read_to_string(path).expect("couldnt read the pdn file");
+                let games = pdn::read_games(&text);
+                positions.extend(tune::positions_from_games(&games, args.get("skip", 8)));
+            }
+            false => {
+                let samples = nn::load_samples(path).expect("couldnt read the samples");
+                positions.extend(tune::positions_from_samples(&samples));
+            }
+        }
+    }
+    println!("{} quiet positions", positions.len());
+
+    let weights = match args.get_str("init") {
+        Some(path) => Weights::load(path).expect("couldnt read the weights"),
+        None => Weights::default(),
+    };
+
+    let scale = tune::fit_scale(&positions, &weights);
+    println!(
+        "scale {:.3}  error {:.5}",
+        scale,
+        tune::error(&positions, &weights, scale)
+    );
+
+    let weights = tune::tune(
+        &positions,
+        weights,
+        scale,
+        args.get("passes", 100),
+        |pass, error, _| println!("pass {:>3}  error {:.5}", pass, error),
+    );
+
+    for (name, value) in TERM_NAMES.iter().zip(weights.0) {
+        println!("{:>10} {}", name, value);
+    }
+
+    weights.save(&out).expect("couldnt write the weights");
+    println!("wrote {}", out);
+}
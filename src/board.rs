@@ -1,32 +1,38 @@
-use crate::utils::CollectArray;
-use std::thread;
-
 mod move_;
 mod piece;
 mod utils;
+mod variant;
 
 pub use move_::Move;
 pub use piece::Piece;
+pub use variant::Variant;
 
-use self::move_::find_direction_offset;
 use crate::ai;
 
+// 40 moves each with only kings moving and nothing taken is a draw
+const DRAW_MOVE_LIMIT: u8 = 80;
+
 #[derive(Copy, Clone, Eq, Debug, PartialEq)]
 pub enum Player {
-    Computer,
+    Computer(ai::Difficulty),
     User,
 }
 
 #[derive(Clone)]
 pub struct Manager {
-    pub board: [Piece; 64],
+    pub board: Vec<Piece>,
+    pub size: usize,          // squares along each side of the board
     pub players: [Player; 2], // blue, red
+    pub variant: Variant,
+    pub huffing: bool, // captures are optional but a skipped one can be huffed
 
     pub gameover: bool,
     pub winner: Piece,
 
-    made_moves: Vec<(Move, bool, u8)>, // Move, Kill move present, moves without kills
+    num_squares_to_edge: Vec<[usize; 8]>,
+    made_moves: Vec<(Move, bool, u8, Vec<usize>)>, // Move, Kill move present, moves without kills, huffable
     kill_move_present: bool,
+    huffable: Vec<usize>, // opponent pieces that skipped a capture last move
     moves_without_kill: u8,
     turn: usize,
 }
@@ -39,13 +45,24 @@ impl Default for Manager {
 
 impl Manager {
     pub fn new() -> Self {
+        Self::with_variant(Variant::Standard)
+    }
+
+    pub fn with_variant(variant: Variant) -> Self {
+        let size = variant.board_size();
+
         let mut manager = Manager {
-            board: (0..64).map(|_| Piece::Empty).collect_array(),
+            board: vec![Piece::Empty; size * size],
+            size,
+            num_squares_to_edge: utils::squares_to_edge(size),
             players: [Player::User, Player::User],
+            variant,
+            huffing: false,
             made_moves: Vec::new(),
             turn: 0usize,
 
             kill_move_present: false,
+            huffable: Vec::new(),
             winner: Piece::Empty,
             gameover: false,
 
@@ -67,6 +84,27 @@ impl Manager {
         }
     }
 
+    // zobrist hash of the pieces, the side to move and any huffable pieces
+    pub fn hash(&self) -> u64 {
+        let side = match self.turn % 2 {
+            0usize => 0,
+            _ => utils::RED_TO_MOVE_KEY,
+        };
+
+        let hash = self
+            .board
+            .iter()
+            .enumerate()
+            .fold(side, |hash, (index, piece)| {
+                hash ^ utils::zobrist_key(index, piece)
+            });
+
+        // the same board has other moves when pieces can be huffed
+        self.huffable
+            .iter()
+            .fold(hash, |hash, index| hash ^ utils::huffable_key(*index))
+    }
+
     pub fn get_pieces(&self, side: Piece) -> Vec<(Piece, usize)> {
         let mut pieces: Vec<(Piece, usize)> = Vec::new();
         for (index, piece) in self.board.iter().enumerate() {
@@ -81,31 +119,63 @@ impl Manager {
 
     fn setup_side(&mut self, start: usize, end: usize, piece: Piece) {
         for i in start..end {
-            let is_even = (i % 2) == 0;
-
-            for j in 0..4 {
-                let index = {
-                    let mut ans = (i * 8) + (j * 2);
-                    if is_even {
-                        ans += 1
-                    }
-
-                    ans
-                };
-
-                self.board[index] = piece;
+            for j in 0..self.size {
+                // only the dark squares are used unless the variant plays on all of them
+                if self.variant.is_orthogonal() || (i + j) % 2 == 1 {
+                    self.board[i * self.size + j] = piece;
+                }
             }
         }
     }
 
     pub fn setup_pieces(&mut self) {
-        self.setup_side(0, 3, Piece::Red(false));
-        self.setup_side(5, 8, Piece::Blue(false));
-        // println!("{:?}", self.get_pieces(Piece::Blue(false)))
+        let size = self.size;
+
+        if self.variant.is_orthogonal() {
+            self.setup_side(1, 3, Piece::Red(false));
+            self.setup_side(size - 3, size - 1, Piece::Blue(false));
+        } else {
+            // two empty rows in the middle, 3 rows each on 8x8 and 5 on 12x12
+            self.setup_side(0, size / 2 - 1, Piece::Red(false));
+            self.setup_side(size / 2 + 1, size, Piece::Blue(false));
+        }
+    }
+
+    // replaces the position with `board` and `side` to move, the game history is lost
+    pub fn set_position(&mut self, board: Vec<Piece>, side: Piece) {
+        assert_eq!(board.len(), self.size * self.size);
+
+        self.board = board;
+        self.turn = if side.is_red() { 1 } else { 0 };
+        self.made_moves.clear();
+        self.huffable.clear();
+        self.moves_without_kill = 0;
+        self.gameover = false;
+        self.winner = Piece::Empty;
+
+        self.refresh_state()
     }
 
     pub fn play_move(&mut self, selected_move: Move) {
+        if selected_move.huff {
+            return self.play_huff(selected_move);
+        }
+
+        let huffable = if self.huffing && self.kill_move_present && selected_move.kills.is_empty() {
+            // every piece that could have captured, the moved one is found where it ended up
+            self.capturers()
+                .into_iter()
+                .map(|x| match x == selected_move.start {
+                    true => selected_move.end,
+                    false => x,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
         let mut piece = self.board[selected_move.start];
+        let man_moved = piece.is_king() == Some(false);
         self.board[selected_move.start] = Piece::Empty;
 
         if selected_move.should_king {
@@ -118,25 +188,70 @@ impl Manager {
             self.board[*index] = Piece::Empty;
         }
 
-        self.moves_without_kill = match selected_move.kills.is_empty() {
-            false => self.moves_without_kill + 1, // increment by 1 if kill is present
-            true => 0,                            // set the selected moves to 0
+        // reset when something is taken or a man moves, counted otherwise
+        let moves_without_kill = self.moves_without_kill;
+        self.moves_without_kill = match selected_move.kills.is_empty() && !man_moved {
+            true => moves_without_kill + 1,
+            false => 0,
         };
 
         self.made_moves.push((
             selected_move,
             self.kill_move_present,
-            self.moves_without_kill,
+            moves_without_kill,
+            std::mem::replace(&mut self.huffable, huffable),
         ));
 
         self.update_state()
     }
 
+    fn play_huff(&mut self, huff: Move) {
+        for (index, _) in &huff.kills {
+            self.board[*index] = Piece::Empty;
+        }
+
+        self.made_moves.push((
+            huff,
+            self.kill_move_present,
+            self.moves_without_kill,
+            std::mem::take(&mut self.huffable),
+        ));
+
+        // the same side still has to make its move
+        self.refresh_state()
+    }
+
+    // every move since the start or the last `set_position`, oldest first
+    pub fn played_moves(&self) -> Vec<Move> {
+        self.made_moves.iter().map(|x| x.0.clone()).collect()
+    }
+
+    // the position before the first of those moves
+    pub fn initial(&self) -> Manager {
+        let mut manager = self.clone();
+        manager.players = [Player::User, Player::User];
+
+        while !manager.made_moves.is_empty() {
+            manager.undo_move();
+        }
+
+        manager
+    }
+
     pub fn undo_move(&mut self) {
-        let (last_move, kill_move_present, moves_without_kill) = self.made_moves.pop().unwrap();
+        let (last_move, kill_move_present, moves_without_kill, huffable) =
+            self.made_moves.pop().unwrap();
         self.kill_move_present = kill_move_present;
         self.moves_without_kill = moves_without_kill;
-        self.turn -= 1;
+        self.huffable = huffable;
+
+        if !last_move.huff {
+            self.turn -= 1;
+        }
+
+        // a position that had a move played from it cant have been over
+        self.gameover = false;
+        self.winner = Piece::Empty;
 
         let mut piece = self.board[last_move.end];
         self.board[last_move.end] = Piece::Empty;
@@ -152,96 +267,177 @@ impl Manager {
         }
     }
 
+    fn should_king(&self, piece: &Piece, end: usize) -> bool {
+        match piece {
+            Piece::Blue(false) => end < self.size,
+            Piece::Red(false) => end >= self.size * (self.size - 1),
+            _ => false,
+        }
+    }
+
+    // empty squares next to the piece in the directions it moves, a cheap stand in for
+    // counting its moves
+    pub fn mobility(&self, index: usize) -> usize {
+        let piece = self.board[index];
+
+        self.variant
+            .move_directions(&piece)
+            .iter()
+            .filter(|x| {
+                let offset = move_::move_offset(**x, self.size);
+                self.num_squares_to_edge[index][**x] > 0
+                    && self.board[(index as isize + offset) as usize].is_empty()
+            })
+            .count()
+    }
+
     fn sliding_moves(&self, index: usize) -> Vec<Move> {
         let piece = self.board[index];
+        let flying = piece.is_king() == Some(true) && self.variant.flying_kings();
         let mut sliding_moves: Vec<Move> = Vec::new();
 
-        for offset_index in move_::find_direction_offset(&piece) {
-            if utils::NUM_SQUARES_TO_EDGE[index][offset_index] == 0 {
-                continue;
-            }
+        for &offset_index in self.variant.move_directions(&piece) {
+            let move_offset: isize = move_::move_offset(offset_index, self.size);
+            let mut end = index;
 
-            let end: usize = (index as i8 + move_::move_offset(offset_index)) as usize;
+            for _ in 0..self.num_squares_to_edge[index][offset_index] {
+                end = (end as isize + move_offset) as usize;
 
-            if !self.board[end].is_empty() {
-                continue;
-            }
+                if !self.board[end].is_empty() {
+                    break;
+                }
 
-            let should_king: bool = match piece {
-                Piece::Blue(false) => end < 8,
-                Piece::Red(false) => end > 56,
-                _ => false,
-            };
+                sliding_moves.push(Move::new_move(
+                    index,
+                    end,
+                    self.should_king(&piece, end),
+                    None,
+                ));
 
-            sliding_moves.push(Move::new_move(index, end, should_king, None))
+                if !flying {
+                    break;
+                }
+            }
         }
 
         sliding_moves
     }
 
-    fn killing_move(&self, index: usize) -> Vec<Move> {
-        let piece = self.board[index];
+    // single jumps for `piece` (which started the move on `start`) standing on `from`,
+    // as (direction, kill index, landing index)
+    fn jumps(
+        &self,
+        piece: &Piece,
+        start: usize,
+        from: usize,
+        kills: &[(usize, Piece)],
+        last_direction: Option<usize>,
+    ) -> Vec<(usize, usize, usize)> {
+        let flying = piece.is_king() == Some(true) && self.variant.flying_kings();
+        let killed = |square: usize| kills.iter().any(|(index, _)| *index == square);
+        let is_free = |square: usize| {
+            square == start
+                || self.board[square].is_empty()
+                || (self.variant.removes_captured_immediately() && killed(square))
+        };
 
-        let mut moves: Vec<Move> = Vec::new();
-        let directions = find_direction_offset(&piece);
+        let mut jumps = Vec::new();
 
-        let mut attack_moves: Vec<Move> = Vec::new();
+        for &offset_index in self.variant.capture_directions(piece) {
+            // turning straight back would go over the piece that was just taken
+            if last_direction.map(utils::opposite_direction) == Some(offset_index) {
+                continue;
+            }
 
-        for offset_index in directions.clone() {
-            let move_offset: i8 = move_::move_offset(offset_index);
+            let move_offset: isize = move_::move_offset(offset_index, self.size);
+            let mut distance = self.num_squares_to_edge[from][offset_index];
+            let mut kill_index = from;
 
-            if utils::NUM_SQUARES_TO_EDGE[index][offset_index] >= 2 {
-                let kill_index = (index as i8 + move_offset) as usize;
-                let move_to_index = (kill_index as i8 + move_offset) as usize;
+            // find the first piece in this direction, kings that fly can start far away
+            let found = loop {
+                if distance == 0 {
+                    break false;
+                }
 
-                let should_king: bool = match piece {
-                    Piece::Blue(false) => move_to_index < 8,
-                    Piece::Red(false) => move_to_index > 56,
-                    _ => false,
-                };
+                kill_index = (kill_index as isize + move_offset) as usize;
+                distance -= 1;
 
-                if self.board[move_to_index].is_empty()
-                    && self.board[kill_index].match_piece(&piece.opposite())
-                {
-                    attack_moves.push(Move::new_move(
-                        index,
-                        move_to_index,
-                        should_king,
-                        Some((kill_index, self.board[kill_index])),
-                    ));
+                if !is_free(kill_index) {
+                    break true;
+                }
+
+                if !flying {
+                    break false;
+                }
+            };
+
+            if !found
+                || killed(kill_index)
+                || !self.board[kill_index].match_piece(&piece.opposite())
+            {
+                continue;
+            }
+
+            let mut move_to_index = kill_index;
+
+            while distance > 0 {
+                move_to_index = (move_to_index as isize + move_offset) as usize;
+                distance -= 1;
+
+                if !is_free(move_to_index) {
+                    break;
+                }
+
+                jumps.push((offset_index, kill_index, move_to_index));
+
+                if !flying {
+                    break;
                 }
             }
         }
 
-        while !attack_moves.is_empty() {
-            let attack = attack_moves.pop().unwrap();
+        jumps
+    }
+
+    fn killing_move(&self, index: usize) -> Vec<Move> {
+        let piece = self.board[index];
+
+        let mut moves: Vec<Move> = Vec::new();
+        let mut attack_moves: Vec<(Move, usize)> = Vec::new();
+
+        for (offset_index, kill_index, move_to_index) in self.jumps(&piece, index, index, &[], None)
+        {
+            attack_moves.push((
+                Move::new_move(
+                    index,
+                    move_to_index,
+                    self.should_king(&piece, move_to_index),
+                    Some((kill_index, self.board[kill_index])),
+                ),
+                offset_index,
+            ));
+        }
+
+        while let Some((attack, last_direction)) = attack_moves.pop() {
             let mut can_kill_more = false;
 
-            for offset_index in directions.clone() {
-                let move_offset: i8 = move_::move_offset(offset_index);
-
-                if utils::NUM_SQUARES_TO_EDGE[attack.end][offset_index] >= 2 {
-                    let kill_index = (attack.end as i8 + move_offset) as usize;
-                    let move_to_index = (kill_index as i8 + move_offset) as usize;
-
-                    let should_king: bool = match piece {
-                        Piece::Blue(false) => move_to_index < 8,
-                        Piece::Red(false) => move_to_index > 56,
-                        _ => false,
-                    };
-
-                    if self.board[move_to_index].is_empty()
-                        && self.board[kill_index].match_piece(&piece.opposite())
-                    {
-                        attack_moves.push(attack.extend(
-                            move_to_index,
-                            (kill_index, self.board[kill_index]),
-                            should_king,
-                        ));
-
-                        can_kill_more = true;
-                    }
-                }
+            for (offset_index, kill_index, move_to_index) in self.jumps(
+                &piece,
+                index,
+                attack.end,
+                &attack.kills,
+                Some(last_direction),
+            ) {
+                attack_moves.push((
+                    attack.extend(
+                        move_to_index,
+                        (kill_index, self.board[kill_index]),
+                        self.should_king(&piece, move_to_index),
+                    ),
+                    offset_index,
+                ));
+
+                can_kill_more = true;
             }
 
             if !can_kill_more {
@@ -255,12 +451,39 @@ impl Manager {
     pub fn piece_moves(&self, index: usize) -> Vec<Move> {
         let piece = self.board[index];
 
-        // check if currently player turn
+        if self.players[self.turn % 2] != Player::User {
+            return Vec::new();
+        }
+
+        // the opponents pieces can only be clicked to huff them
+        if self.huffable.contains(&index) {
+            return vec![Move::new_huff(index, piece)];
+        }
+
         // check if piece is same as current turn
-        if self.players[self.turn % 2] != Player::User || !piece.match_piece(&self.current_side()) {
+        if !piece.match_piece(&self.current_side()) {
             return Vec::new();
         }
 
+        if self.variant.max_capture() && self.kill_move_present {
+            // whether this piece's captures are long enough depends on every other piece
+            return self
+                .legal_moves()
+                .into_iter()
+                .filter(|x| x.start == index)
+                .collect();
+        }
+
+        self.moves_from(index)
+    }
+
+    fn moves_from(&self, index: usize) -> Vec<Move> {
+        if self.kill_move_present && self.huffing {
+            let mut moves = self.killing_move(index);
+            moves.extend(self.sliding_moves(index));
+            return moves;
+        }
+
         if self.kill_move_present {
             return self.killing_move(index);
         }
@@ -268,47 +491,101 @@ impl Manager {
         self.sliding_moves(index)
     }
 
+    // pieces of the side to move that have a capture
+    fn capturers(&self) -> Vec<usize> {
+        self.get_pieces(self.current_side())
+            .iter()
+            .filter(|(piece, index)| !self.jumps(piece, *index, *index, &[], None).is_empty())
+            .map(|(_, index)| *index)
+            .collect()
+    }
+
+    // the side to move has a capture it has to take
+    pub fn must_capture(&self) -> bool {
+        self.kill_move_present
+    }
+
+    // whether `side` would have a capture if it was its turn
+    pub fn threatens_capture(&self, side: Piece) -> bool {
+        self.get_pieces(side)
+            .iter()
+            .any(|(piece, index)| !self.jumps(piece, *index, *index, &[], None).is_empty())
+    }
+
+    // every move the side to move can play, whoever is playing it
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = self
+            .huffable
+            .iter()
+            .map(|index| Move::new_huff(*index, self.board[*index]))
+            .collect();
+
+        for (_, index) in self.get_pieces(self.current_side()) {
+            moves.extend(self.moves_from(index));
+        }
+
+        if self.variant.max_capture() {
+            // huffs and (with huffing) sliding moves arent captures so they are left alone
+            let most_kills = moves
+                .iter()
+                .filter(|x| !x.huff)
+                .map(|x| x.kills.len())
+                .max()
+                .unwrap_or(0);
+            moves.retain(|x| x.huff || x.kills.is_empty() || x.kills.len() == most_kills);
+        }
+
+        moves
+    }
+
+    fn make_ai_play(&mut self, difficulty: ai::Difficulty) {
+        if let Some(best_move) = ai::find_best_move_with(self, &difficulty.options()) {
+            self.play_move(best_move);
+        }
+    }
+
+    // hands a side over to the computer or back, which moves straight away if its its turn
+    pub fn set_player(&mut self, side: Piece, player: Player) {
+        let index = match side {
+            Piece::Red(_) => 1,
+            _ => 0,
+        };
+        self.players[index] = player;
 
-    fn make_ai_play(&mut self) {
-        let best_move = ai::find_best_move(self);
-        self.play_move(best_move);
+        if index == self.turn % 2 && !self.gameover {
+            self.refresh_state()
+        }
     }
 
     fn update_state(&mut self) {
-        self.kill_move_present = false;
         self.turn += 1;
+        self.refresh_state()
+    }
 
-        for (piece, index) in self.get_pieces(self.current_side()) {
-            for offset_index in find_direction_offset(&piece) {
-                let move_offset: i8 = move_::move_offset(offset_index);
-
-                if utils::NUM_SQUARES_TO_EDGE[index][offset_index] >= 2 {
-                    let kill_index = (index as i8 + move_offset) as usize;
-                    let move_to_index = (kill_index as i8 + move_offset) as usize;
+    fn refresh_state(&mut self) {
+        self.kill_move_present = !self.capturers().is_empty();
 
-                    if self.board[move_to_index].is_empty()
-                        && self.board[kill_index].match_piece(&piece.opposite())
-                    {
-                        self.kill_move_present = true;
-                        break;
-                    }
-                }
-            }
+        if self.moves_without_kill >= DRAW_MOVE_LIMIT {
+            self.gameover = true;
+            self.winner = Piece::Empty;
+            return;
+        }
 
-            if self.kill_move_present {
-                break;
-            }
+        if self.legal_moves().is_empty() {
+            self.gameover = true;
+            self.winner = self.variant.winner(self.current_side());
+            return;
         }
 
-        if self.players[self.turn % 2] == Player::Computer {
-            self.make_ai_play()
+        if let Player::Computer(difficulty) = self.players[self.turn % 2] {
+            self.make_ai_play(difficulty)
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Manager, Piece};
+    use super::{Manager, Move, Piece, Variant};
 
     #[test]
     fn test_get_pieces() {
@@ -329,5 +606,137 @@ mod tests {
         assert_eq!(manager.piece_moves(42_usize).len(), 2);
     }
 
-    // todo: test ai
+    #[test]
+    fn test_turkish_setup() {
+        let manager = Manager::with_variant(Variant::Turkish);
+
+        assert_eq!(manager.get_pieces(Piece::Red(false)).len(), 16);
+        assert_eq!(manager.get_pieces(Piece::Blue(false)).len(), 16);
+
+        // only forward, the squares to the side are taken
+        assert_eq!(manager.piece_moves(42_usize).len(), 1);
+        assert!(manager.piece_moves(50_usize).is_empty());
+    }
+
+    #[test]
+    fn test_turkish_flying_king_max_capture() {
+        let mut manager = Manager::with_variant(Variant::Turkish);
+        manager.board.iter_mut().for_each(|x| *x = Piece::Empty);
+        manager.board[56] = Piece::Blue(true);
+        manager.board[32] = Piece::Red(false);
+        manager.board[58] = Piece::Red(false);
+        manager.board[28] = Piece::Red(false);
+        manager.kill_move_present = true;
+
+        let moves = manager.legal_moves();
+
+        assert_eq!(moves.len(), 6);
+        assert!(moves.iter().all(|x| x.kills.len() == 2));
+        assert_eq!(manager.piece_moves(56).len(), 6);
+    }
+
+    #[test]
+    fn test_board_sizes() {
+        for (variant, size, pieces) in [
+            (Variant::Standard, 8, 12),
+            (Variant::International, 10, 20),
+            (Variant::Canadian, 12, 30),
+        ] {
+            let manager = Manager::with_variant(variant);
+
+            assert_eq!(manager.board.len(), size * size);
+            assert_eq!(manager.get_pieces(Piece::Red(false)).len(), pieces);
+            assert_eq!(manager.get_pieces(Piece::Blue(false)).len(), pieces);
+            // the front men can all move, the ones on the edge only one way
+            assert_eq!(manager.legal_moves().len(), size - 1);
+        }
+    }
+
+    #[test]
+    fn test_canadian_men_capture_backwards() {
+        let mut manager = Manager::with_variant(Variant::Canadian);
+        manager.board.iter_mut().for_each(|x| *x = Piece::Empty);
+        manager.board[77] = Piece::Blue(false);
+        manager.board[88] = Piece::Red(false);
+        manager.board[90] = Piece::Red(false);
+        manager.board[92] = Piece::Red(false);
+        manager.kill_move_present = true;
+
+        // taking 88 is a single capture, going back over 90 and then 92 takes two
+        let moves = manager.legal_moves();
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].end, 81);
+        assert_eq!(moves[0].through, vec![103]);
+        assert_eq!(moves[0].kills.len(), 2);
+    }
+
+    #[test]
+    fn test_huffing() {
+        let mut manager = Manager::new();
+        manager.huffing = true;
+        manager.board.iter_mut().for_each(|x| *x = Piece::Empty);
+        manager.board[42] = Piece::Blue(false);
+        manager.board[46] = Piece::Blue(false);
+        manager.board[33] = Piece::Red(false);
+        manager.board[1] = Piece::Red(false);
+        manager.refresh_state();
+
+        // the capture is there but so are the sliding moves
+        assert_eq!(manager.legal_moves().len(), 4);
+
+        // blue ignores the capture with a different piece
+        manager.play_move(Move::new_move(46, 37, false, None));
+        assert_eq!(manager.current_side(), Piece::Red(false));
+        assert_eq!(
+            manager.piece_moves(42),
+            vec![Move::new_huff(42, Piece::Blue(false))]
+        );
+
+        manager.play_move(Move::new_huff(42, Piece::Blue(false)));
+        assert!(manager.board[42].is_empty());
+        assert_eq!(manager.current_side(), Piece::Red(false));
+        assert!(manager.legal_moves().iter().all(|x| !x.huff));
+
+        manager.undo_move();
+        assert_eq!(manager.board[42], Piece::Blue(false));
+        assert_eq!(manager.current_side(), Piece::Red(false));
+        assert_eq!(manager.legal_moves().iter().filter(|x| x.huff).count(), 1);
+    }
+
+    #[test]
+    fn test_hash() {
+        let mut manager = Manager::new();
+        let start = manager.hash();
+
+        manager.play_move(manager.piece_moves(42).remove(0));
+        assert_ne!(manager.hash(), start);
+
+        manager.undo_move();
+        assert_eq!(manager.hash(), start);
+        assert_ne!(Manager::with_variant(Variant::Turkish).hash(), start);
+    }
+
+    #[test]
+    fn test_gameover_winner() {
+        for (variant, winner) in [
+            (Variant::Standard, Piece::Blue(false)),
+            (Variant::Giveaway, Piece::Red(false)),
+        ] {
+            let mut manager = Manager::with_variant(variant);
+            manager.board.iter_mut().for_each(|x| *x = Piece::Empty);
+            manager.board[42] = Piece::Blue(false);
+            manager.board[33] = Piece::Red(false);
+
+            // blue takes the last red piece so red is out of moves
+            manager.play_move(Move::new_move(42, 24, false, Some((33, Piece::Red(false)))));
+
+            assert!(manager.gameover);
+            assert_eq!(manager.winner, winner);
+
+            manager.undo_move();
+            assert!(!manager.gameover);
+            assert_eq!(manager.board[33], Piece::Red(false));
+        }
+    }
 }
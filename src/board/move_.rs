@@ -1,21 +1,8 @@
 use crate::board::{piece::Piece, utils::*};
 
-pub fn find_direction_offset(piece: &Piece) -> std::ops::Range<usize> {
-    let mut start = 0;
-    let mut end = 4;
-
-    match piece {
-        Piece::Blue(false) => start = 2,
-        Piece::Red(false) => end = 2,
-        _ => {}
-    }
-
-    start..end
-}
-
-pub fn move_offset(offset_index: usize) -> i8 {
-    let i = if offset_index / 2 == 1 { -1 } else { 1 };
-    DIRECTIONAL_OFFSET[offset_index % 2] * i
+pub fn move_offset(offset_index: usize, size: usize) -> isize {
+    let (file, rank) = DIRECTIONS[offset_index];
+    file as isize + rank as isize * size as isize
 }
 
 #[derive(Clone, Eq, Debug, PartialEq)]
@@ -25,6 +12,7 @@ pub struct Move {
     pub should_king: bool,
     pub through: Vec<usize>,
     pub end: usize,
+    pub huff: bool, // taking away a piece that didnt capture when it could, doesnt use up the turn
 }
 
 impl Move {
@@ -41,6 +29,7 @@ impl Move {
             through,
             should_king,
             end,
+            huff: false,
         }
     }
 
@@ -50,10 +39,9 @@ impl Move {
         should_king: bool,
         kill: Option<(usize, Piece)>,
     ) -> Self {
-        let kills = if kill == None {
-            Vec::new()
-        } else {
-            vec![kill.unwrap()]
+        let kills = match kill {
+            Some(kill) => vec![kill],
+            None => Vec::new(),
         };
         let through = Vec::new();
 
@@ -63,6 +51,18 @@ impl Move {
             through,
             should_king,
             end,
+            huff: false,
+        }
+    }
+
+    pub fn new_huff(index: usize, piece: Piece) -> Self {
+        Move {
+            start: index,
+            kills: vec![(index, piece)],
+            through: Vec::new(),
+            should_king: false,
+            end: index,
+            huff: true,
         }
     }
 }
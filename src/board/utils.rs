@@ -1,44 +1,103 @@
-pub const DIRECTIONAL_OFFSET: [i8; 2] = [7, 9];
-
-const fn calc_const_matrix() -> [[usize; 4]; 64] {
-    let mut matrix = [[0usize; 4]; 64];
-    let mut row = 0usize;
-    while row < 8 {
-        let mut column = 0;
-        while column < 8 {
-            let index = column * 8 + row;
-            let dist_north = 7 - column;
+use crate::board::piece::Piece;
+
+// (file, rank) steps, the four diagonals first and then the four orthogonals
+pub const DIRECTIONS: [(i8, i8); 8] = [
+    (-1, 1),
+    (1, 1),
+    (1, -1),
+    (-1, -1),
+    (0, 1),
+    (1, 0),
+    (0, -1),
+    (-1, 0),
+];
+
+pub const DIAGONALS: [usize; 4] = [0, 1, 2, 3];
+pub const ORTHOGONALS: [usize; 4] = [4, 5, 6, 7];
+
+// forward for red is down the board and for blue up the board
+pub const RED_DIAGONALS: [usize; 2] = [0, 1];
+pub const BLUE_DIAGONALS: [usize; 2] = [2, 3];
+pub const RED_ORTHOGONALS: [usize; 3] = [4, 5, 7]; // forward and sideways
+pub const BLUE_ORTHOGONALS: [usize; 3] = [6, 5, 7];
+
+pub const fn opposite_direction(direction: usize) -> usize {
+    (direction + 2) % 4 + (direction / 4) * 4
+}
+
+// splitmix64, spreads the bits of x out to make up zobrist keys
+const fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub const RED_TO_MOVE_KEY: u64 = mix(u64::MAX);
+
+pub fn zobrist_key(index: usize, piece: &Piece) -> u64 {
+    let kind = match piece {
+        Piece::Blue(false) => 0,
+        Piece::Blue(true) => 1,
+        Piece::Red(false) => 2,
+        Piece::Red(true) => 3,
+        Piece::Empty => return 0,
+    };
+
+    mix((index * 4 + kind) as u64)
+}
+
+pub fn huffable_key(index: usize) -> u64 {
+    mix(u64::MAX - 1 - index as u64)
+}
+
+pub fn squares_to_edge(size: usize) -> Vec<[usize; 8]> {
+    let mut matrix = vec![[0usize; 8]; size * size];
+
+    for row in 0..size {
+        for column in 0..size {
+            let index = column * size + row;
+            let dist_north = size - 1 - column;
             let dist_south = column;
-            let dist_east = 7 - row;
+            let dist_east = size - 1 - row;
             let dist_west = row;
 
             matrix[index] = [
-                if dist_north < dist_west {
-                    dist_north
-                } else {
-                    dist_west
-                },
-                if dist_north < dist_east {
-                    dist_north
-                } else {
-                    dist_east
-                },
-                if dist_south < dist_east {
-                    dist_south
-                } else {
-                    dist_east
-                },
-                if dist_south < dist_west {
-                    dist_south
-                } else {
-                    dist_west
-                },
+                dist_north.min(dist_west),
+                dist_north.min(dist_east),
+                dist_south.min(dist_east),
+                dist_south.min(dist_west),
+                dist_north,
+                dist_east,
+                dist_south,
+                dist_west,
             ];
-            column += 1;
         }
-        row += 1
     }
+
     matrix
 }
 
-pub const NUM_SQUARES_TO_EDGE: [[usize; 4]; 64] = calc_const_matrix();
+#[cfg(test)]
+mod tests {
+    use super::{opposite_direction, squares_to_edge, DIRECTIONS};
+
+    #[test]
+    fn check_opposite_direction() {
+        for size in [8, 10, 12] {
+            let num_squares_to_edge = squares_to_edge(size);
+            let last = size * size - 1;
+
+            for direction in 0..8 {
+                let (file, rank) = DIRECTIONS[direction];
+                let opposite = opposite_direction(direction);
+
+                assert_eq!(DIRECTIONS[opposite], (-file, -rank));
+                assert_eq!(
+                    num_squares_to_edge[0][direction],
+                    num_squares_to_edge[last][opposite]
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::board::{piece::Piece, utils::*};
+
+#[derive(Copy, Clone, Eq, Debug, Default, PartialEq)]
+pub enum Variant {
+    #[default]
+    Standard,
+    Giveaway,      // antidraughts, the side that cant move anymore wins
+    Turkish,       // orthogonal moves on all 64 squares with flying kings
+    International, // 10x10, men capture backwards and kings fly
+    Canadian,      // international rules on a 12x12 board
+}
+
+impl Variant {
+    pub const ALL: [Variant; 5] = [
+        Variant::Standard,
+        Variant::Giveaway,
+        Variant::Turkish,
+        Variant::International,
+        Variant::Canadian,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Giveaway => "giveaway",
+            Variant::Turkish => "turkish",
+            Variant::International => "international",
+            Variant::Canadian => "canadian",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        Variant::ALL
+            .into_iter()
+            .find(|x| x.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn is_losing(&self) -> bool {
+        matches!(self, Variant::Giveaway)
+    }
+
+    pub fn board_size(&self) -> usize {
+        match self {
+            Variant::International => 10,
+            Variant::Canadian => 12,
+            _ => 8,
+        }
+    }
+
+    pub fn is_orthogonal(&self) -> bool {
+        matches!(self, Variant::Turkish)
+    }
+
+    pub fn flying_kings(&self) -> bool {
+        !matches!(self, Variant::Standard | Variant::Giveaway)
+    }
+
+    // a capture has to take as many pieces as possible
+    pub fn max_capture(&self) -> bool {
+        !matches!(self, Variant::Standard | Variant::Giveaway)
+    }
+
+    pub fn men_capture_backwards(&self) -> bool {
+        matches!(self, Variant::International | Variant::Canadian)
+    }
+
+    // captured pieces are taken off one by one instead of at the end of the move,
+    // so they can be passed over again later in the same capture
+    pub fn removes_captured_immediately(&self) -> bool {
+        matches!(self, Variant::Turkish)
+    }
+
+    // indexes into DIRECTIONS the piece can step in
+    pub fn move_directions(&self, piece: &Piece) -> &'static [usize] {
+        match (piece, self.is_orthogonal()) {
+            (Piece::Empty, _) => &[],
+            (Piece::Blue(false), false) => &BLUE_DIAGONALS,
+            (Piece::Red(false), false) => &RED_DIAGONALS,
+            (_, false) => &DIAGONALS,
+            (Piece::Blue(false), true) => &BLUE_ORTHOGONALS,
+            (Piece::Red(false), true) => &RED_ORTHOGONALS,
+            (_, true) => &ORTHOGONALS,
+        }
+    }
+
+    pub fn capture_directions(&self, piece: &Piece) -> &'static [usize] {
+        if self.men_capture_backwards() && !piece.is_empty() {
+            return &DIAGONALS;
+        }
+
+        self.move_directions(piece)
+    }
+
+    // who wins when `stuck_side` has no moves left
+    pub fn winner(&self, stuck_side: Piece) -> Piece {
+        let stuck_side = stuck_side.base_form();
+
+        if self.is_losing() {
+            stuck_side
+        } else {
+            stuck_side.opposite()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Piece, Variant};
+
+    #[test]
+    fn check_winner() {
+        let test_case = vec![
+            (Variant::Standard, Piece::Red(false), Piece::Blue(false)),
+            (Variant::Standard, Piece::Blue(true), Piece::Red(false)),
+            (Variant::Giveaway, Piece::Red(false), Piece::Red(false)),
+            (Variant::Giveaway, Piece::Blue(true), Piece::Blue(false)),
+        ];
+
+        for (variant, stuck_side, winner) in test_case {
+            assert_eq!(variant.winner(stuck_side), winner)
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use crate::ai::{self, SearchOptions};
+use crate::board::{Manager, Move, Piece, Variant};
+use crate::pdn::{self, Game};
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+// opening book for standard checkers, moves are kept by the hash of the position they are
+// played from. on disk its the entry count followed by every entry sorted by hash:
+// hash (u64), move count (u16) and for each move start, end (u8) and weight (u32)
+
+const MAGIC: &[u8; 4] = b"CKBK";
+
+#[derive(Copy, Clone, Eq, Debug, Default, PartialEq)]
+pub enum BookSelection {
+    #[default]
+    Best, // always the move with the most weight
+    Weighted, // picked at random by weight so games vary
+}
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub struct BookMove {
+    pub start: usize,
+    pub end: usize,
+    pub weight: u32,
+}
+
+#[derive(Default)]
+pub struct Book {
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl fmt::Debug for Book {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Book")
+            .field("positions", &self.entries.len())
+            .finish()
+    }
+}
+
+impl Book {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn add(&mut self, hash: u64, book_move: &Move, weight: u32) {
+        let moves = self.entries.entry(hash).or_default();
+
+        match moves
+            .iter_mut()
+            .find(|x| x.start == book_move.start && x.end == book_move.end)
+        {
+            Some(existing) => existing.weight += weight,
+            None => moves.push(BookMove {
+                start: book_move.start,
+                end: book_move.end,
+                weight,
+            }),
+        }
+    }
+
+    pub fn moves(&self, hash: u64) -> &[BookMove] {
+        self.entries.get(&hash).map_or(&[], |x| x.as_slice())
+    }
+
+    // the first `plies` moves of every game, moves by the side that went on to win count
+    // double and moves by the loser not at all
+    pub fn from_games(games: &[Game], plies: usize) -> Self {
+        let mut book = Book::default();
+
+        for game in games {
+            if !game.is_standard_from_start() {
+                continue;
+            }
+
+            let winner = game.winner();
+            let mut manager = Manager::new();
+
+            for text in game.moves.iter().take(plies) {
+                let book_move = match pdn::parse_move(&manager, text) {
+                    Some(book_move) => book_move,
+                    None => break,
+                };
+
+                let weight = match winner {
+                    Some(Piece::Empty) | None => 1,
+                    Some(winner) if winner.match_piece(&manager.current_side()) => 2,
+                    Some(_) => 0,
+                };
+
+                if weight > 0 {
+                    book.add(manager.hash(), &book_move, weight);
+                }
+
+                manager.play_move(book_move);
+            }
+        }
+
+        book
+    }
+
+    // the engines own choice in every position reachable within `plies` moves
+    pub fn from_search(
+        options: &SearchOptions,
+        plies: usize,
+        mut progress: impl FnMut(usize),
+    ) -> Self {
+        let mut book = Book::default();
+        let mut seen = HashSet::new();
+        let mut manager = Manager::new();
+
+        book.expand(&mut manager, options, plies, &mut seen, &mut progress);
+
+        book
+    }
+
+    fn expand(
+        &mut self,
+        manager: &mut Manager,
+        options: &SearchOptions,
+        plies: usize,
+        seen: &mut HashSet<u64>,
+        progress: &mut impl FnMut(usize),
+    ) {
+        if plies == 0 || manager.gameover || !seen.insert(manager.hash()) {
+            return;
+        }
+
+        if let Some(best_move) = ai::find_best_move_with(manager, options) {
+            self.add(manager.hash(), &best_move, 1);
+            progress(self.len());
+        }
+
+        // every reply is followed so the book still knows what to do when the opponent
+        // doesnt play the expected move
+        for possible_move in manager.legal_moves() {
+            manager.play_move(possible_move);
+            self.expand(manager, options, plies - 1, seen, progress);
+            manager.undo_move();
+        }
+    }
+
+    pub fn probe<R: Rng>(
+        &self,
+        manager: &Manager,
+        selection: BookSelection,
+        rng: &mut R,
+    ) -> Option<Move> {
+        if manager.variant != Variant::Standard || manager.huffing {
+            return None;
+        }
+
+        let legal_moves = manager.legal_moves();
+        let candidates: Vec<(Move, u32)> = self
+            .moves(manager.hash())
+            .iter()
+            .filter(|x| x.weight > 0)
+            .filter_map(|x| {
+                legal_moves
+                    .iter()
+                    .find(|y| y.start == x.start && y.end == x.end)
+                    .map(|y| (y.clone(), x.weight))
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let index = match selection {
+            BookSelection::Best => (0..candidates.len())
+                .max_by_key(|x| (candidates[*x].1, std::cmp::Reverse(*x)))
+                .unwrap(),
+            BookSelection::Weighted => WeightedIndex::new(candidates.iter().map(|x| x.1))
+                .unwrap()
+                .sample(rng),
+        };
+
+        Some(candidates[index].0.clone())
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        file.write_all(MAGIC)?;
+        file.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+
+        let mut hashes: Vec<&u64> = self.entries.keys().collect();
+        hashes.sort();
+
+        for hash in hashes {
+            let moves = &self.entries[hash];
+
+            file.write_all(&hash.to_le_bytes())?;
+            file.write_all(&(moves.len() as u16).to_le_bytes())?;
+
+            for book_move in moves {
+                file.write_all(&[book_move.start as u8, book_move.end as u8])?;
+                file.write_all(&book_move.weight.to_le_bytes())?;
+            }
+        }
+
+        file.flush()
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an opening book",
+            ));
+        }
+
+        let mut entries = HashMap::new();
+        let mut count = [0u8; 4];
+        file.read_exact(&mut count)?;
+
+        for _ in 0..u32::from_le_bytes(count) {
+            let mut hash = [0u8; 8];
+            let mut move_count = [0u8; 2];
+            file.read_exact(&mut hash)?;
+            file.read_exact(&mut move_count)?;
+
+            let mut moves = Vec::new();
+
+            for _ in 0..u16::from_le_bytes(move_count) {
+                let mut squares = [0u8; 2];
+                let mut weight = [0u8; 4];
+                file.read_exact(&mut squares)?;
+                file.read_exact(&mut weight)?;
+
+                moves.push(BookMove {
+                    start: squares[0] as usize,
+                    end: squares[1] as usize,
+                    weight: u32::from_le_bytes(weight),
+                });
+            }
+
+            entries.insert(u64::from_le_bytes(hash), moves);
+        }
+
+        Ok(Book { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Book, BookSelection};
+    use crate::board::Manager;
+    use crate::pdn::{self, read_games};
+    use rand::thread_rng;
+
+    #[test]
+    fn check_from_games() {
+        let games =
+            read_games("1. 11-15 23-19 2. 8-11 1-0\n1. 11-15 22-17 0-1\n1. 9-14 23-19 1/2-1/2\n");
+        let book = Book::from_games(&games, 2);
+        let manager = Manager::new();
+
+        // 11-15 won once and lost once, 9-14 was drawn
+        let moves = book.moves(manager.hash());
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves.iter().map(|x| x.weight).sum::<u32>(), 3);
+
+        let best = book
+            .probe(&manager, BookSelection::Best, &mut thread_rng())
+            .unwrap();
+        assert_eq!(pdn::move_to_string(&manager, &best), "11-15");
+
+        for _ in 0..10 {
+            let book_move = book
+                .probe(&manager, BookSelection::Weighted, &mut thread_rng())
+                .unwrap();
+            assert!(manager.legal_moves().contains(&book_move));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// `--name value` options for the binaries, a `--name` without a value is a flag.
+// positional arguments have to come before any flag, otherwise they are taken as its value
+pub struct Args {
+    options: HashMap<String, String>,
+    pub positional: Vec<String>,
+}
+
+impl Args {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut options = HashMap::new();
+        let mut positional = Vec::new();
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = match args.peek() {
+                        Some(value) if !value.starts_with("--") => args.next().unwrap(),
+                        _ => "true".to_owned(),
+                    };
+                    options.insert(name.to_owned(), value);
+                }
+                None => positional.push(arg),
+            }
+        }
+
+        Args {
+            options,
+            positional,
+        }
+    }
+
+    pub fn get<T: FromStr>(&self, name: &str, default: T) -> T {
+        match self.options.get(name) {
+            Some(value) => value
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid value for --{}: {}", name, value)),
+            None => default,
+        }
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|x| x.as_str())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.get(name, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Args;
+
+    #[test]
+    fn check_parse() {
+        let args = Args::parse(
+            ["games.pdn", "--pairs", "4", "--verbose", "--depth", "3"]
+                .iter()
+                .map(|x| x.to_string()),
+        );
+
+        assert_eq!(args.get("pairs", 1), 4);
+        assert_eq!(args.get("depth", 6u8), 3);
+        assert_eq!(args.get("missing", 7), 7);
+        assert!(args.flag("verbose"));
+        assert!(!args.flag("quiet"));
+        assert_eq!(args.positional, vec!["games.pdn"]);
+    }
+}
@@ -0,0 +1,711 @@
+use crate::board::{Manager, Piece, Variant};
+use crate::pdn;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+// endgame databases for standard checkers. every position is stored with blue to move,
+// positions with red to move are looked up with the board turned around and the colours
+// swapped. values are 0 for a draw and otherwise the number of plies to the end plus 1,
+// even distances are losses for the side to move and odd ones wins
+
+const MAGIC: &[u8; 4] = b"CKDB";
+const MEN_SQUARES: usize = 28; // men are never on their own promotion row
+
+#[derive(Copy, Clone, Eq, Hash, Debug, PartialEq, PartialOrd, Ord)]
+pub struct Material {
+    pub blue_men: u8,
+    pub blue_kings: u8,
+    pub red_men: u8,
+    pub red_kings: u8,
+}
+
+impl Material {
+    fn of(board: &[Piece]) -> Self {
+        let count = |piece: Piece| board.iter().filter(|x| **x == piece).count() as u8;
+
+        Material {
+            blue_men: count(Piece::Blue(false)),
+            blue_kings: count(Piece::Blue(true)),
+            red_men: count(Piece::Red(false)),
+            red_kings: count(Piece::Red(true)),
+        }
+    }
+
+    pub fn pieces(&self) -> usize {
+        (self.blue_men + self.blue_kings + self.red_men + self.red_kings) as usize
+    }
+
+    fn men(&self) -> usize {
+        (self.blue_men + self.red_men) as usize
+    }
+
+    fn flip(&self) -> Self {
+        Material {
+            blue_men: self.red_men,
+            blue_kings: self.red_kings,
+            red_men: self.blue_men,
+            red_kings: self.blue_kings,
+        }
+    }
+
+    // every split of up to `max_pieces` pieces where both sides still have something
+    fn all(max_pieces: usize) -> Vec<Material> {
+        let mut materials = Vec::new();
+        let max = max_pieces as u8;
+
+        for blue_men in 0..=max {
+            for blue_kings in 0..=max - blue_men {
+                for red_men in 0..=max - blue_men - blue_kings {
+                    for red_kings in 0..=max - blue_men - blue_kings - red_men {
+                        let material = Material {
+                            blue_men,
+                            blue_kings,
+                            red_men,
+                            red_kings,
+                        };
+
+                        if blue_men + blue_kings > 0 && red_men + red_kings > 0 {
+                            materials.push(material);
+                        }
+                    }
+                }
+            }
+        }
+
+        materials
+    }
+}
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub enum Outcome {
+    Win(u8), // plies until the game is over, 0 if the database only knows win/loss/draw
+    Loss(u8),
+    Draw,
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+// colex rank of a sorted set of positions
+fn rank(positions: &[usize]) -> usize {
+    positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| binomial(*position, i + 1))
+        .sum()
+}
+
+fn unrank(mut rank: usize, count: usize) -> Vec<usize> {
+    let mut positions = vec![0; count];
+
+    for i in (1..=count).rev() {
+        let mut position = i - 1;
+        while binomial(position + 1, i) <= rank {
+            position += 1;
+        }
+
+        rank -= binomial(position, i);
+        positions[i - 1] = position;
+    }
+
+    positions
+}
+
+// maps between the 64 square board and the 32 playable squares in number order
+struct Indexer {
+    squares: Vec<usize>,
+}
+
+impl Indexer {
+    fn new() -> Self {
+        let manager = Manager::new();
+        let squares: Vec<usize> = (1..=32)
+            .map(|number| pdn::square_index(&manager, number).unwrap())
+            .collect();
+
+        Indexer { squares }
+    }
+
+    fn slice_size(&self, material: &Material) -> usize {
+        let free = 32 - material.men();
+
+        binomial(MEN_SQUARES, material.blue_men as usize)
+            * binomial(MEN_SQUARES, material.red_men as usize)
+            * binomial(free, material.blue_kings as usize)
+            * binomial(
+                free - material.blue_kings as usize,
+                material.red_kings as usize,
+            )
+    }
+
+    // position of a board with `material` on it inside its slice
+    fn index(&self, material: &Material, board: &[Piece]) -> usize {
+        let squares_with = |piece: Piece| -> Vec<usize> {
+            self.squares
+                .iter()
+                .enumerate()
+                .filter(|(_, index)| board[**index] == piece)
+                .map(|(square, _)| square)
+                .collect()
+        };
+
+        let blue_men = squares_with(Piece::Blue(false));
+        // red men start counting after blues promotion row
+        let red_men: Vec<usize> = squares_with(Piece::Red(false))
+            .iter()
+            .map(|x| x - 4)
+            .collect();
+
+        let free: Vec<usize> = (0..32)
+            .filter(|square| board[self.squares[*square]].is_king() != Some(false))
+            .collect();
+        let blue_kings: Vec<usize> = squares_with(Piece::Blue(true))
+            .iter()
+            .map(|x| free.iter().position(|y| y == x).unwrap())
+            .collect();
+
+        let free: Vec<usize> = free
+            .into_iter()
+            .filter(|square| board[self.squares[*square]] != Piece::Blue(true))
+            .collect();
+        let red_kings: Vec<usize> = squares_with(Piece::Red(true))
+            .iter()
+            .map(|x| free.iter().position(|y| y == x).unwrap())
+            .collect();
+
+        let free = 32 - material.men();
+        let mut index = rank(&blue_men);
+        index = index * binomial(MEN_SQUARES, material.red_men as usize) + rank(&red_men);
+        index = index * binomial(free, material.blue_kings as usize) + rank(&blue_kings);
+        index = index
+            * binomial(
+                free - material.blue_kings as usize,
+                material.red_kings as usize,
+            )
+            + rank(&red_kings);
+
+        index
+    }
+
+    // the board at `index`, none if the men would be on top of each other
+    fn board(&self, material: &Material, mut index: usize) -> Option<Vec<Piece>> {
+        let free = 32 - material.men();
+        let red_kings_size = binomial(
+            free - material.blue_kings as usize,
+            material.red_kings as usize,
+        );
+        let blue_kings_size = binomial(free, material.blue_kings as usize);
+        let red_men_size = binomial(MEN_SQUARES, material.red_men as usize);
+
+        let red_kings = unrank(index % red_kings_size, material.red_kings as usize);
+        index /= red_kings_size;
+        let blue_kings = unrank(index % blue_kings_size, material.blue_kings as usize);
+        index /= blue_kings_size;
+        let red_men = unrank(index % red_men_size, material.red_men as usize);
+        let blue_men = unrank(index / red_men_size, material.blue_men as usize);
+
+        let mut board = vec![Piece::Empty; 64];
+
+        for square in blue_men {
+            board[self.squares[square]] = Piece::Blue(false);
+        }
+
+        for square in red_men {
+            if !board[self.squares[square + 4]].is_empty() {
+                return None;
+            }
+            board[self.squares[square + 4]] = Piece::Red(false);
+        }
+
+        let free: Vec<usize> = (0..32)
+            .filter(|square| board[self.squares[*square]].is_empty())
+            .collect();
+        for position in blue_kings.iter() {
+            board[self.squares[free[*position]]] = Piece::Blue(true);
+        }
+
+        let free: Vec<usize> = free
+            .into_iter()
+            .filter(|square| board[self.squares[*square]].is_empty())
+            .collect();
+        for position in red_kings {
+            board[self.squares[free[position]]] = Piece::Red(true);
+        }
+
+        Some(board)
+    }
+}
+
+// where a move from a position being solved leads
+enum Successor {
+    Stuck,         // the opponent has no moves left
+    Inside(usize), // a position of the group being solved
+    Outside(u16),  // the value of a position in a slice already solved
+}
+
+// the same position with red to move becomes this one with blue to move
+fn flipped(board: &[Piece]) -> Vec<Piece> {
+    (0..board.len())
+        .map(|index| board[board.len() - 1 - index].opposite())
+        .collect()
+}
+
+pub struct EndgameDb {
+    pub max_pieces: usize,
+    pub distance: bool, // false if only win/loss/draw was kept
+    slices: HashMap<Material, Vec<u8>>,
+    indexer: Indexer,
+}
+
+impl fmt::Debug for EndgameDb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EndgameDb")
+            .field("max_pieces", &self.max_pieces)
+            .field("distance", &self.distance)
+            .field("slices", &self.slices.len())
+            .finish()
+    }
+}
+
+impl EndgameDb {
+    // solves every position with up to `max_pieces` pieces, smallest first since
+    // captures and promotions always lead into slices that are already done
+    pub fn generate(max_pieces: usize, mut progress: impl FnMut(&Material, usize)) -> Self {
+        let mut db = EndgameDb {
+            max_pieces,
+            distance: true,
+            slices: HashMap::new(),
+            indexer: Indexer::new(),
+        };
+
+        let mut materials = Material::all(max_pieces);
+        materials.sort_by_key(|x| (x.pieces(), x.men(), *x));
+
+        let mut manager = Manager::new();
+
+        for material in materials {
+            if db.slices.contains_key(&material) {
+                continue;
+            }
+
+            // a slice and its flipped twin lead into each other so they are solved together
+            let group = match material.flip() == material {
+                true => vec![material],
+                false => vec![material, material.flip()],
+            };
+
+            let values = db.solve(&group, &mut manager);
+
+            for (material, values) in group.into_iter().zip(values) {
+                progress(&material, values.len());
+                db.slices.insert(material, values);
+            }
+        }
+
+        db
+    }
+
+    fn lookup(&self, board: &[Piece]) -> u8 {
+        let material = Material::of(board);
+        self.slices[&material][self.indexer.index(&material, board)]
+    }
+
+    // where the moves from `node` lead, none if the index isnt a position
+    fn successors(
+        &self,
+        group: &[Material],
+        offsets: &[usize],
+        node: usize,
+        manager: &mut Manager,
+    ) -> Option<Vec<Successor>> {
+        let slice = offsets.iter().rposition(|x| *x <= node).unwrap();
+        let board = self.indexer.board(&group[slice], node - offsets[slice])?;
+        manager.set_position(board, Piece::Blue(false));
+
+        let mut successors = Vec::new();
+
+        for possible_move in manager.legal_moves() {
+            manager.play_move(possible_move);
+
+            successors.push(match manager.gameover {
+                true => Successor::Stuck,
+                false => {
+                    let board = flipped(&manager.board);
+                    let child = Material::of(&board);
+
+                    match group.iter().position(|x| *x == child) {
+                        Some(slice) => {
+                            Successor::Inside(offsets[slice] + self.indexer.index(&child, &board))
+                        }
+                        None => Successor::Outside(self.lookup(&board) as u16),
+                    }
+                }
+            });
+
+            manager.undo_move();
+        }
+
+        Some(successors)
+    }
+
+    // retrograde analysis. the moves are generated once, moves inside the group are kept
+    // both ways round and moves out of it only for the best value they lead to. positions
+    // are then settled in order of their value, each one passing it back to the positions
+    // leading into it: a loss makes them a win one ply further off, a win counts off one of
+    // their moves and the last one to go makes them a loss. what never settles is a draw
+    fn solve(&self, group: &[Material], manager: &mut Manager) -> Vec<Vec<u8>> {
+        let mut offsets = vec![0];
+        for material in group {
+            offsets.push(offsets.last().unwrap() + self.indexer.slice_size(material));
+        }
+        let total = *offsets.last().unwrap();
+
+        let mut child_starts = Vec::with_capacity(total + 1);
+        let mut children: Vec<u32> = Vec::new();
+        let mut remaining = vec![0u8; total]; // moves inside the group not known to lose yet
+        let mut quickest_win = vec![0u16; total]; // through a move out of the group, 0 for none
+        let mut longest_loss = vec![0u16; total];
+        let mut drawn = vec![false; total]; // a move out of the group draws
+        let mut done = vec![false; total];
+        let mut values = vec![0u16; total];
+
+        // positions waiting to be settled, by value. a position can be in here more than
+        // once, the first time it comes up is its value
+        let mut queue: Vec<Vec<u32>> = Vec::new();
+        let push = |queue: &mut Vec<Vec<u32>>, value: u16, node: usize| {
+            if queue.len() <= value as usize {
+                queue.resize(value as usize + 1, Vec::new());
+            }
+            queue[value as usize].push(node as u32);
+        };
+
+        for node in 0..total {
+            child_starts.push(children.len());
+
+            let successors = match self.successors(group, &offsets, node, manager) {
+                Some(successors) => successors,
+                None => {
+                    done[node] = true;
+                    continue;
+                }
+            };
+
+            for successor in &successors {
+                let value = match successor {
+                    Successor::Stuck => 1,
+                    Successor::Inside(child) => {
+                        children.push(*child as u32);
+                        remaining[node] += 1;
+                        continue;
+                    }
+                    Successor::Outside(value) => *value,
+                };
+
+                match value {
+                    0 => drawn[node] = true,
+                    value if value % 2 == 1 => {
+                        quickest_win[node] = match quickest_win[node] {
+                            0 => value,
+                            win => win.min(value),
+                        }
+                    }
+                    value => longest_loss[node] = longest_loss[node].max(value),
+                }
+            }
+
+            if quickest_win[node] > 0 {
+                push(&mut queue, quickest_win[node] + 1, node);
+            } else if remaining[node] == 0 {
+                match drawn[node] {
+                    true => done[node] = true,
+                    false => push(&mut queue, longest_loss[node] + 1, node),
+                }
+            }
+        }
+        child_starts.push(children.len());
+
+        // the same moves the other way round
+        let mut parent_starts = vec![0; total + 1];
+        for child in &children {
+            parent_starts[*child as usize + 1] += 1;
+        }
+        for node in 0..total {
+            parent_starts[node + 1] += parent_starts[node];
+        }
+
+        let mut parents = vec![0u32; children.len()];
+        let mut filled = parent_starts.clone();
+        for node in 0..total {
+            for child in &children[child_starts[node]..child_starts[node + 1]] {
+                parents[filled[*child as usize]] = node as u32;
+                filled[*child as usize] += 1;
+            }
+        }
+        drop(children);
+        drop(child_starts);
+
+        let mut value = 0;
+
+        while value < queue.len() {
+            for node in std::mem::take(&mut queue[value]) {
+                let node = node as usize;
+                if done[node] {
+                    continue;
+                }
+
+                done[node] = true;
+                values[node] = value as u16;
+
+                for parent in &parents[parent_starts[node]..parent_starts[node + 1]] {
+                    let parent = *parent as usize;
+                    if done[parent] {
+                        continue;
+                    }
+
+                    match value % 2 == 1 {
+                        true => push(&mut queue, value as u16 + 1, parent),
+                        false => {
+                            remaining[parent] -= 1;
+                            longest_loss[parent] = longest_loss[parent].max(value as u16);
+
+                            if remaining[parent] == 0 && quickest_win[parent] == 0 && !drawn[parent]
+                            {
+                                push(&mut queue, longest_loss[parent] + 1, parent);
+                            }
+                        }
+                    }
+                }
+            }
+
+            value += 1;
+        }
+
+        // distances that dont fit in a byte are cut off, keeping whether its a win or loss
+        let values: Vec<u8> = values
+            .into_iter()
+            .map(|value| match value > 254 {
+                true => 254 - (value % 2) as u8,
+                false => value as u8,
+            })
+            .collect();
+
+        offsets
+            .windows(2)
+            .map(|x| values[x[0]..x[1]].to_vec())
+            .collect()
+    }
+
+    // only knows about standard checkers without house rules
+    pub fn probe(&self, manager: &Manager) -> Option<Outcome> {
+        if manager.variant != Variant::Standard || manager.huffing || manager.gameover {
+            return None;
+        }
+
+        let pieces = manager.get_pieces(Piece::Blue(false)).len()
+            + manager.get_pieces(Piece::Red(false)).len();
+        if pieces > self.max_pieces {
+            return None;
+        }
+
+        let value = match manager.current_side() {
+            Piece::Blue(_) => self.lookup(&manager.board),
+            _ => self.lookup(&flipped(&manager.board)),
+        };
+
+        let distance = match self.distance {
+            true => value.saturating_sub(1),
+            false => 0,
+        };
+
+        match value {
+            0 => Some(Outcome::Draw),
+            value if value % 2 == 0 => Some(Outcome::Win(distance)),
+            _ => Some(Outcome::Loss(distance)),
+        }
+    }
+
+    pub fn save(&self, path: &str, distance: bool) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let distance = distance && self.distance;
+
+        file.write_all(MAGIC)?;
+        file.write_all(&[self.max_pieces as u8, distance as u8])?;
+
+        let mut materials: Vec<&Material> = self.slices.keys().collect();
+        materials.sort();
+
+        for material in materials {
+            let values = &self.slices[material];
+
+            file.write_all(&[
+                material.blue_men,
+                material.blue_kings,
+                material.red_men,
+                material.red_kings,
+            ])?;
+            file.write_all(&(values.len() as u64).to_le_bytes())?;
+
+            if distance {
+                file.write_all(values)?;
+            } else {
+                // 1 for a loss and 2 for a win
+                let values: Vec<u8> = values
+                    .iter()
+                    .map(|x| match x {
+                        0 => 0,
+                        x if x % 2 == 1 => 1,
+                        _ => 2,
+                    })
+                    .collect();
+                file.write_all(&values)?;
+            }
+        }
+
+        file.flush()
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut header = [0u8; 6];
+        file.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not an endgame database"));
+        }
+
+        let indexer = Indexer::new();
+        let mut slices = HashMap::new();
+        let mut material = [0u8; 4];
+
+        loop {
+            match file.read_exact(&mut material) {
+                Ok(()) => {}
+                Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(error) => return Err(error),
+            }
+
+            let material = Material {
+                blue_men: material[0],
+                blue_kings: material[1],
+                red_men: material[2],
+                red_kings: material[3],
+            };
+
+            let mut length = [0u8; 8];
+            file.read_exact(&mut length)?;
+            let length = u64::from_le_bytes(length) as usize;
+
+            if length != indexer.slice_size(&material) {
+                return Err(invalid("slice has the wrong size"));
+            }
+
+            let mut values = vec![0u8; length];
+            file.read_exact(&mut values)?;
+            slices.insert(material, values);
+        }
+
+        Ok(EndgameDb {
+            max_pieces: header[4] as usize,
+            distance: header[5] == 1,
+            slices,
+            indexer,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flipped, EndgameDb, Indexer, Material, Outcome};
+    use crate::board::{Manager, Piece, Variant};
+    use crate::pdn;
+
+    #[test]
+    fn check_index_round_trip() {
+        let indexer = Indexer::new();
+
+        for material in Material::all(3) {
+            for index in 0..indexer.slice_size(&material) {
+                if let Some(board) = indexer.board(&material, index) {
+                    assert_eq!(Material::of(&board), material);
+                    assert_eq!(indexer.index(&material, &board), index);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_generate_and_probe() {
+        let db = EndgameDb::generate(2, |_, _| {});
+        let mut manager = Manager::new();
+
+        // one king each cant get anywhere
+        let mut board = vec![Piece::Empty; 64];
+        board[1] = Piece::Blue(true);
+        board[62] = Piece::Red(true);
+        manager.set_position(board, Piece::Red(false));
+        assert_eq!(db.probe(&manager), Some(Outcome::Draw));
+
+        // whoever moves takes the other king
+        let mut board = vec![Piece::Empty; 64];
+        board[28] = Piece::Blue(true);
+        board[37] = Piece::Red(true);
+        manager.set_position(board.clone(), Piece::Blue(false));
+        assert_eq!(db.probe(&manager), Some(Outcome::Win(1)));
+
+        manager.set_position(board, Piece::Red(false));
+        assert_eq!(db.probe(&manager), Some(Outcome::Win(1)));
+    }
+
+    #[test]
+    fn check_distance_across_slices() {
+        let db = EndgameDb::generate(2, |_, _| {});
+
+        // both moves of the man promote it, and the red king wins the kings ending that
+        // follows in 3 plies at best
+        let manager = pdn::from_fen(Variant::Standard, "B:B25:WK17").unwrap();
+        assert_eq!(db.probe(&manager), Some(Outcome::Loss(4)));
+
+        // and every value agrees with the best move out of the position
+        let mut manager = Manager::new();
+
+        for (material, values) in &db.slices {
+            for (index, value) in values.iter().enumerate() {
+                let board = match db.indexer.board(material, index) {
+                    Some(board) => board,
+                    None => continue,
+                };
+                manager.set_position(board, Piece::Blue(false));
+
+                let children: Vec<u8> = manager
+                    .legal_moves()
+                    .into_iter()
+                    .map(|x| {
+                        let mut child = manager.clone();
+                        child.play_move(x);
+
+                        match child.gameover {
+                            true => 1,
+                            false => db.lookup(&flipped(&child.board)),
+                        }
+                    })
+                    .collect();
+
+                let expected = match children.iter().filter(|x| *x % 2 == 1).min() {
+                    Some(loss) => loss + 1,
+                    None if children.contains(&0) => 0,
+                    None => children.iter().max().map_or(1, |x| x + 1),
+                };
+
+                assert_eq!(*value, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+pub mod ai;
+pub mod analysis;
+pub mod arena;
+pub mod ballot;
+pub mod board;
+pub mod book;
+pub mod cli;
+pub mod egdb;
+pub mod nn;
+pub mod pdn;
+pub mod pns;
+pub mod puzzle;
+pub mod tt;
+pub mod tune;
+pub mod utils;
@@ -1,24 +1,32 @@
+use ::rand::thread_rng;
+use checkers_ai::cli::Args;
+use checkers_ai::{ai, analysis, ballot, board, nn, pdn, puzzle};
 use macroquad::prelude::*;
 use resources::{load_resources, Resources};
+use std::sync::Arc;
+use std::thread;
 
 const BOARD_OFFSET: f32 = 30f32;
 const BOARD_SIZE: f32 = 640f32;
-const CELL_SIZE: f32 = (BOARD_SIZE / 8f32) as f32;
 
-const PIECE_SCALE: f32 = 0.4f32;
+const PIECE_SCALE: f32 = 0.4f32; // for an 8x8 board, smaller boards scale it down
 
 const WHITE_SQUARES: Color = Color::new(1.00, 1.00, 1.00, 1.00);
 const BLACK_SQUARES: Color = Color::new(0.09, 0.18, 0.21, 1.00);
 
 const END_COLOR: Color = Color::new(0.96, 0.81, 0.16, 1.00);
 const KILL_COLOR: Color = Color::new(0.96, 0.16, 0.16, 1.00);
+const HINT_COLOR: Color = Color::new(0.16, 0.76, 0.36, 1.00);
+
+const HINT_LINES: usize = 3;
+
+const ANALYSIS_PATH: &str = "analysis.pdn";
+const PUZZLES_PATH: &str = "puzzles.pdn";
+const PUZZLE_STATS_PATH: &str = "puzzle_stats.txt";
 
 const CIRCLE_RADIUS: f32 = 10.00;
 
-mod ai;
-pub mod board;
 mod resources;
-pub mod utils;
 
 fn window_conf() -> Conf {
     Conf {
@@ -31,10 +39,27 @@ fn window_conf() -> Conf {
     }
 }
 
-fn draw_board() {
-    for file in 0..8 {
-        for rank in 0..8 {
-            let is_white = (file + rank) % 2 == 0;
+fn cell_size(size: usize) -> f32 {
+    BOARD_SIZE / size as f32
+}
+
+fn piece_scale(size: usize) -> f32 {
+    PIECE_SCALE * 8f32 / size as f32
+}
+
+fn square_center(index: usize, size: usize) -> (f32, f32) {
+    let x: f32 = BOARD_OFFSET + (cell_size(size) * ((index % size) as f32 + 0.5));
+    let y: f32 = BOARD_OFFSET + (cell_size(size) * ((index / size) as f32 + 0.5));
+
+    (x, y)
+}
+
+fn draw_board(size: usize, all_squares: bool) {
+    let cell_size = cell_size(size);
+
+    for file in 0..size {
+        for rank in 0..size {
+            let is_white = !all_squares && (file + rank) % 2 == 0;
             let color: Color = {
                 if is_white {
                     WHITE_SQUARES
@@ -43,10 +68,10 @@ fn draw_board() {
                 }
             };
             draw_rectangle(
-                BOARD_OFFSET + (file as f32 * CELL_SIZE),
-                BOARD_OFFSET + (rank as f32 * CELL_SIZE),
-                CELL_SIZE as f32,
-                CELL_SIZE as f32,
+                BOARD_OFFSET + (file as f32 * cell_size),
+                BOARD_OFFSET + (rank as f32 * cell_size),
+                cell_size,
+                cell_size,
                 color,
             );
         }
@@ -82,7 +107,12 @@ fn draw_scaled_img(img: Texture2D, x: f32, y: f32, scale: f32, should_center: bo
     )
 }
 
-fn draw_pieces(board: &[board::Piece; 64], active_index: &Option<usize>, resources: &Resources) {
+fn draw_pieces(
+    board: &[board::Piece],
+    size: usize,
+    active_index: &Option<usize>,
+    resources: &Resources,
+) {
     let should_not_draw = match active_index {
         Some(index) => index.to_owned(),
         None => 5000usize,
@@ -100,50 +130,414 @@ fn draw_pieces(board: &[board::Piece; 64], active_index: &Option<usize>, resourc
             _ => resources.piece_img(piece),
         };
 
-        let x: f32 = BOARD_OFFSET + (CELL_SIZE * ((index % 8) as f32 + 0.5));
-        let y: f32 = BOARD_OFFSET + (CELL_SIZE * ((index / 8) as f32 + 0.5));
+        let (x, y) = square_center(index, size);
+        let scale = piece_scale(size);
 
         if piece.is_king().unwrap() {
-            let offset = (16f32 * PIECE_SCALE) / 2f32;
-            draw_scaled_img(img, x, y + offset, PIECE_SCALE, true);
-            draw_scaled_img(img, x, y - offset, PIECE_SCALE, true);
+            let offset = (16f32 * scale) / 2f32;
+            draw_scaled_img(img, x, y + offset, scale, true);
+            draw_scaled_img(img, x, y - offset, scale, true);
         } else {
-            draw_scaled_img(img, x, y, PIECE_SCALE, true);
+            draw_scaled_img(img, x, y, scale, true);
+        }
+    }
+}
+
+fn draw_move(shown_move: &board::Move, size: usize, start_color: Color) {
+    let (x, y) = square_center(shown_move.start, size);
+    draw_circle(x, y, CIRCLE_RADIUS, start_color);
+
+    for index in shown_move.through.iter().chain(Some(&shown_move.end)) {
+        let (x, y) = square_center(*index, size);
+        draw_circle(x, y, CIRCLE_RADIUS, END_COLOR);
+    }
+
+    for (kill_index, _) in &shown_move.kills {
+        let (x, y) = square_center(*kill_index, size);
+        draw_circle(x, y, CIRCLE_RADIUS, KILL_COLOR);
+    }
+}
+
+// the engines best lines for the side to move, each with its score
+fn draw_hint(manager: &board::Manager, lines: &[ai::Line]) {
+    if let Some(line) = lines.first() {
+        draw_move(&line.moves[0], manager.size, HINT_COLOR);
+    }
+
+    for (number, line) in lines.iter().enumerate() {
+        let mut position = manager.clone();
+        let moves: Vec<String> = line
+            .moves
+            .iter()
+            .map(|x| {
+                let text = pdn::move_to_string(&position, x);
+                position.play_move(x.clone());
+                text
+            })
+            .collect();
+
+        draw_text(
+            &format!("{:+}  {}", line.score, moves.join(" ")),
+            BOARD_SIZE + BOARD_OFFSET * 2f32,
+            BOARD_OFFSET * 4f32 + number as f32 * 28f32,
+            24f32,
+            BLACK,
+        );
+    }
+}
+
+// the move played in the reviewed position and the better one if it wasnt good
+fn draw_review(analysis: &analysis::Analysis, position: &board::Manager, ply: usize) {
+    let x = BOARD_SIZE + BOARD_OFFSET * 2f32;
+    let mut lines: Vec<String> = analysis.summary().lines().map(|x| x.to_owned()).collect();
+
+    match analysis.moves.get(ply) {
+        Some(analysed) => {
+            draw_move(&analysed.played, position.size, END_COLOR);
+
+            lines.push(format!(
+                "move {}: {}{} {}",
+                ply + 1,
+                pdn::move_to_string(position, &analysed.played),
+                analysed.judgement.symbol(),
+                analysed.judgement.name()
+            ));
+
+            if analysed.judgement != analysis::Judgement::Good {
+                draw_move(&analysed.best, position.size, HINT_COLOR);
+
+                lines.push(format!(
+                    "{} was better, {:+} against {:+}",
+                    pdn::move_to_string(position, &analysed.best),
+                    analysed.best_score,
+                    analysed.score
+                ));
+            }
+        }
+        None => lines.push("end of the game".to_owned()),
+    }
+
+    lines.push(format!(
+        "left/right step through, S saves to {}, escape leaves",
+        ANALYSIS_PATH
+    ));
+
+    for (number, line) in lines.iter().enumerate() {
+        draw_text(
+            line,
+            x,
+            BOARD_OFFSET * 4f32 + number as f32 * 28f32,
+            24f32,
+            BLACK,
+        );
+    }
+}
+
+// what each term of the evaluation adds for either side, under whatever else is shown
+fn draw_explanation(manager: &board::Manager, weights: &ai::Weights) {
+    let explanation = ai::evaluate_explained_with(manager, weights);
+
+    for (number, line) in explanation.lines().iter().enumerate() {
+        draw_text(
+            line,
+            BOARD_SIZE + BOARD_OFFSET * 2f32,
+            BOARD_SIZE / 2f32 + BOARD_OFFSET + number as f32 * 24f32,
+            22f32,
+            BLACK,
+        );
+    }
+}
+
+fn draw_puzzle(
+    attempt: &puzzle::Attempt,
+    step: Option<puzzle::Step>,
+    manager: &board::Manager,
+    stats: &puzzle::PuzzleStats,
+    number: usize,
+    total: usize,
+) {
+    let side = match manager.current_side() {
+        board::Piece::Red(_) => "red",
+        _ => "blue",
+    };
+
+    // once its gone wrong the way on is shown
+    if attempt.failed && !attempt.solved() {
+        if let Some(expected) = attempt.expected() {
+            draw_move(&expected, manager.size, HINT_COLOR);
+        }
+    }
+
+    let lines = [
+        format!(
+            "puzzle {}/{}: {} to {}",
+            number + 1,
+            total,
+            side,
+            attempt.puzzle.goal.name()
+        ),
+        match step {
+            None => "your move".to_owned(),
+            Some(puzzle::Step::Correct) => "correct, keep going".to_owned(),
+            Some(puzzle::Step::Wrong) => "not that one, the move to play is shown".to_owned(),
+            Some(puzzle::Step::Solved) if attempt.failed => "solved, with some help".to_owned(),
+            Some(puzzle::Step::Solved) => "solved!".to_owned(),
+            Some(puzzle::Step::Thinking) => "thinking...".to_owned(),
+        },
+        format!(
+            "solved {}   failed {}   streak {}   best streak {}",
+            stats.solved, stats.failed, stats.streak, stats.best_streak
+        ),
+        "enter for the next puzzle, Q leaves".to_owned(),
+    ];
+
+    for (number, line) in lines.iter().enumerate() {
+        draw_text(
+            line,
+            BOARD_SIZE + BOARD_OFFSET * 2f32,
+            BOARD_OFFSET * 4f32 + number as f32 * 28f32,
+            24f32,
+            BLACK,
+        );
+    }
+}
+
+// user, then every difficulty from the weakest up and back to the user
+// only the first wrong move counts, and a solve without one
+fn record_step(
+    stats: &mut puzzle::PuzzleStats,
+    failed: bool,
+    attempt: &puzzle::Attempt,
+    step: puzzle::Step,
+) {
+    let record = match step {
+        puzzle::Step::Wrong if !failed => Some(false),
+        puzzle::Step::Solved if !attempt.failed => Some(true),
+        _ => None,
+    };
+
+    if let Some(solved) = record {
+        stats.record(solved);
+        if let Err(error) = stats.save(PUZZLE_STATS_PATH) {
+            eprintln!("couldnt save the puzzle stats: {}", error);
         }
     }
 }
 
+fn next_player(player: board::Player) -> board::Player {
+    let levels = ai::Difficulty::ALL;
+
+    match player {
+        board::Player::User => board::Player::Computer(levels[0]),
+        board::Player::Computer(level) => match levels.iter().position(|x| *x == level) {
+            Some(index) if index + 1 < levels.len() => board::Player::Computer(levels[index + 1]),
+            _ => board::Player::User,
+        },
+    }
+}
+
+fn player_name(player: board::Player) -> &'static str {
+    match player {
+        board::Player::User => "you",
+        board::Player::Computer(level) => level.name(),
+    }
+}
+
+// the computer players and hints evaluate with the weights or network given, if any
+//
+//   checkers-ai [--weights tuned.txt] [--network standard.nn]
 #[macroquad::main(window_conf)]
 async fn main() {
+    let args = Args::from_env();
+    let evaluation = ai::SearchOptions {
+        network: args
+            .get_str("network")
+            .map(|path| Arc::new(nn::Network::load(path).expect("couldnt read the network"))),
+        weights: match args.get_str("weights") {
+            Some(path) => ai::Weights::load(path).expect("couldnt read the weights"),
+            None => ai::Weights::default(),
+        },
+        ..Default::default()
+    };
+
     let resources = load_resources().await;
     let mut manager = board::Manager::new();
+    let mut huffing = false;
+    let ballots = ballot::ballots();
     let mut active_index: Option<usize> = None;
     let mut active_moves: Vec<board::Move> = Vec::new();
+    let mut hint: Option<(u64, Vec<ai::Line>)> = None; // for the position with that hash
+    let mut hinting: Option<ai::BackgroundSearch> = None;
+
+    // the computer sides are played from here, the manager itself only has users so the
+    // searches can run in the background
+    let mut players = [board::Player::User; 2];
+    let mut thinking: Option<ai::BackgroundSearch> = None;
+    let mut pondering: Option<ai::Ponder> = None;
+    let mut ponder = true;
+    let mut explain = false;
+
+    // a finished game can be gone through move by move once the engine has graded it
+    let mut analysing: Option<thread::JoinHandle<analysis::Analysis>> = None;
+    let mut review: Option<(analysis::Analysis, usize)> = None; // and the move looked at
+
+    // puzzles are played on the main board and the computer sides sit out meanwhile
+    let puzzles = puzzle::read_puzzles(&std::fs::read_to_string(PUZZLES_PATH).unwrap_or_default());
+    let mut puzzle_stats = puzzle::PuzzleStats::load(PUZZLE_STATS_PATH).unwrap_or_default();
+    let mut attempt: Option<(puzzle::Attempt, Option<puzzle::Step>)> = None; // and the last step
+    let mut puzzle_index = 0;
 
     loop {
+        let turn = manager.current_side().is_red() as usize;
+
+        if let (board::Player::Computer(level), None) = (players[turn], &attempt) {
+            if thinking.is_none() && !manager.gameover {
+                // on a ponder hit the search has had a head start
+                thinking = match pondering.take().and_then(|x| x.resolve(&manager)) {
+                    Some(search) => Some(search),
+                    None => Some(ai::BackgroundSearch::start(
+                        &manager,
+                        level.options_with(&evaluation),
+                    )),
+                };
+            }
+
+            if thinking.as_ref().is_some_and(|x| x.is_finished()) {
+                if let Some(result) = thinking.take().unwrap().wait() {
+                    manager.play_move(result.best_move.clone());
+
+                    if ponder && players[1 - turn] == board::Player::User && !manager.gameover {
+                        let options = level.options_with(&evaluation);
+                        pondering = ai::Ponder::start(&manager, &result, &options);
+                    }
+                }
+            }
+        }
+
+        // a hint for a position thats been moved on from is no use
+        if hinting.as_ref().is_some_and(|x| x.hash != manager.hash()) {
+            hinting.take().unwrap().stop();
+        }
+
+        if hinting.as_ref().is_some_and(|x| x.is_finished()) {
+            let search = hinting.take().unwrap();
+            let hash = search.hash;
+            hint = search.wait().map(|x| (hash, x.lines));
+        }
+
+        // a puzzle move off the solution and the defence to it are searched in the background
+        if let Some((current, step)) = attempt.as_mut() {
+            let failed = current.failed;
+
+            if let Some(outcome) = current.poll(&mut manager) {
+                record_step(&mut puzzle_stats, failed, current, outcome);
+                *step = Some(outcome);
+            }
+        }
+
+        if analysing.as_ref().is_some_and(|x| x.is_finished()) {
+            let finished = analysing
+                .take()
+                .unwrap()
+                .join()
+                .expect("the analysis panicked");
+            review = Some((finished, 0));
+        }
+
         clear_background(Color::from_rgba(254, 241, 208, 255));
         draw_texture(resources.background, 0f32, 0f32, WHITE);
 
-        draw_board();
+        let size = manager.size;
+        let cell_size = cell_size(size);
+
+        draw_board(size, manager.variant.is_orthogonal());
 
         // todo: add indicator for the last move
 
-        draw_pieces(&manager.board, &active_index, &resources);
+        let reviewed = review.as_ref().map(|(x, ply)| x.position(*ply));
+        draw_pieces(
+            &reviewed.as_ref().unwrap_or(&manager).board,
+            size,
+            &active_index,
+            &resources,
+        );
+
+        match (&review, &reviewed, &attempt) {
+            (Some((finished, ply)), Some(position), _) => draw_review(finished, position, *ply),
+            (_, _, Some((current, step))) => draw_puzzle(
+                current,
+                *step,
+                &manager,
+                &puzzle_stats,
+                puzzle_index,
+                puzzles.len(),
+            ),
+            _ => {
+                if let Some((_, lines)) = hint.as_ref().filter(|x| x.0 == manager.hash()) {
+                    draw_hint(&manager, lines);
+                }
+            }
+        }
+
+        if explain {
+            draw_explanation(reviewed.as_ref().unwrap_or(&manager), &evaluation.weights);
+        }
+
+        draw_text(
+            &format!(
+                "blue: {}   red: {}   pondering: {}",
+                player_name(players[0]),
+                player_name(players[1]),
+                if ponder { "on" } else { "off" }
+            ),
+            BOARD_SIZE + BOARD_OFFSET * 2f32,
+            BOARD_SIZE + BOARD_OFFSET,
+            24f32,
+            BLACK,
+        );
+
+        if analysing.is_some() {
+            draw_text(
+                "analysing the game...",
+                BOARD_SIZE + BOARD_OFFSET * 2f32,
+                BOARD_OFFSET * 4f32,
+                24f32,
+                BLACK,
+            );
+        }
+
+        if manager.gameover {
+            let text = match manager.winner {
+                board::Piece::Blue(_) => "Blue wins!",
+                board::Piece::Red(_) => "Red wins!",
+                board::Piece::Empty => "Draw!",
+            };
+            draw_text(
+                text,
+                BOARD_SIZE + BOARD_OFFSET * 2f32,
+                BOARD_OFFSET * 2f32,
+                48f32,
+                BLACK,
+            );
+        }
 
         let (mx, my) = mouse_position();
         let inside_board: bool = {
-            let x = mx - BOARD_OFFSET as f32;
-            let y = my - BOARD_OFFSET as f32;
+            let x = mx - BOARD_OFFSET;
+            let y = my - BOARD_OFFSET;
 
-            x > 0f32 && x < BOARD_SIZE as f32 && y > 0f32 && y < BOARD_SIZE as f32
+            x > 0f32 && x < BOARD_SIZE && y > 0f32 && y < BOARD_SIZE
         };
 
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let x = mx - BOARD_OFFSET as f32;
-            let y = my - BOARD_OFFSET as f32;
+        if is_mouse_button_pressed(MouseButton::Left)
+            && (players[turn] == board::Player::User || attempt.is_some())
+            && review.is_none()
+        {
+            let x = mx - BOARD_OFFSET;
+            let y = my - BOARD_OFFSET;
 
             if x > 0f32 && x < BOARD_SIZE && y > 0f32 && y < BOARD_SIZE {
-                let index = (y / CELL_SIZE) as usize * 8 + (x / CELL_SIZE) as usize;
+                let index = (y / cell_size) as usize * size + (x / cell_size) as usize;
 
                 if !manager.board[index].is_empty() {
                     active_moves = manager.piece_moves(index);
@@ -154,7 +548,7 @@ async fn main() {
 
         if let Some(drag_index) = active_index {
             if is_mouse_button_down(MouseButton::Left) {
-                let piece = &manager.board[drag_index as usize];
+                let piece = &manager.board[drag_index];
 
                 let img = match piece {
                     board::Piece::Empty => {
@@ -164,42 +558,167 @@ async fn main() {
                 };
 
                 for index in active_moves.iter().map(|x| x.end) {
-                    let x: f32 = BOARD_OFFSET + (CELL_SIZE * ((index % 8) as f32 + 0.5));
-                    let y: f32 = BOARD_OFFSET + (CELL_SIZE * ((index / 8) as f32 + 0.5));
+                    let (x, y) = square_center(index, size);
 
                     draw_circle(x, y, CIRCLE_RADIUS, END_COLOR);
                 }
 
                 for (kill_index, _) in active_moves.iter().flat_map(|x| &x.kills) {
-                    let x: f32 = BOARD_OFFSET + (CELL_SIZE * ((kill_index % 8) as f32 + 0.5));
-                    let y: f32 = BOARD_OFFSET + (CELL_SIZE * ((kill_index / 8) as f32 + 0.5));
+                    let (x, y) = square_center(*kill_index, size);
 
                     draw_circle(x, y, CIRCLE_RADIUS, KILL_COLOR);
                 }
 
-
                 for through_index in active_moves.iter().flat_map(|x| &x.through) {
-                    let x: f32 = BOARD_OFFSET + (CELL_SIZE * ((through_index % 8) as f32 + 0.5));
-                    let y: f32 = BOARD_OFFSET + (CELL_SIZE * ((through_index / 8) as f32 + 0.5));
+                    let (x, y) = square_center(*through_index, size);
 
                     draw_circle(x, y, CIRCLE_RADIUS, END_COLOR);
                 }
 
-                draw_scaled_img(img, mx, my, 0.4, true)
+                draw_scaled_img(img, mx, my, piece_scale(size), true)
             }
 
             if is_mouse_button_released(MouseButton::Left) {
-                let x = mx - BOARD_OFFSET as f32;
-                let y = my - BOARD_OFFSET as f32;
+                let x = mx - BOARD_OFFSET;
+                let y = my - BOARD_OFFSET;
 
                 if inside_board {
-                    let index = (y / CELL_SIZE) as usize * 8 + (x / CELL_SIZE as f32) as usize;
+                    let index = (y / cell_size) as usize * size + (x / cell_size) as usize;
 
                     if let Some(move_index) = active_moves.iter().position(|x| x.end == index) {
-                        manager.play_move(active_moves[move_index].clone())
+                        let chosen = active_moves[move_index].clone();
+
+                        match attempt.as_mut() {
+                            Some((current, step)) if !current.solved() => {
+                                let failed = current.failed;
+                                let outcome = current.play(&mut manager, chosen);
+
+                                record_step(&mut puzzle_stats, failed, current, outcome);
+                                *step = Some(outcome);
+                            }
+                            Some(_) => {}
+                            None => manager.play_move(chosen),
+                        }
+                    }
+                }
+
+                active_index = None;
+                active_moves.clear()
+            }
+        }
+
+        // start a new game, N for normal checkers, G for giveaway, T for turkish,
+        // I for international and C for canadian, B for a random three move ballot
+        // and H restarts with huffing turned on/off. space asks the engine for a hint,
+        // 1 and 2 hand blue or red over to the computer and step through the difficulties
+        // and P turns thinking on the players time on/off. E shows/hides the evaluation
+        // term by term. once the game is over A has the engine go through it
+        let key = get_last_key_pressed();
+
+        if key == Some(KeyCode::E) {
+            explain = !explain;
+        }
+
+        if key == Some(KeyCode::A) && manager.gameover && analysing.is_none() && review.is_none() {
+            let finished = manager.clone();
+            analysing = Some(thread::spawn(move || {
+                analysis::analyse(&finished, &ai::SearchOptions::default(), |_, _| {})
+            }));
+        }
+
+        if let Some((finished, ply)) = review.as_mut() {
+            match key {
+                Some(KeyCode::Left) => *ply = ply.saturating_sub(1),
+                Some(KeyCode::Right) => *ply = (*ply + 1).min(finished.moves.len()),
+                Some(KeyCode::S) => {
+                    if let Err(error) = std::fs::write(ANALYSIS_PATH, finished.to_pdn()) {
+                        eprintln!("couldnt save the analysis: {}", error);
                     }
                 }
+                Some(KeyCode::Escape) => review = None,
+                _ => {}
+            }
+        }
+
+        for (index, key_code) in [(0, KeyCode::Key1), (1, KeyCode::Key2)] {
+            if key == Some(key_code) {
+                players[index] = next_player(players[index]);
+            }
+        }
+
+        if key == Some(KeyCode::P) {
+            ponder = !ponder;
+        }
+
+        if key == Some(KeyCode::Space) && !manager.gameover && hinting.is_none() {
+            let options = ai::SearchOptions {
+                multi_pv: HINT_LINES,
+                network: evaluation.network.clone(),
+                weights: evaluation.weights,
+                ..Default::default()
+            };
+            hinting = Some(ai::BackgroundSearch::start(&manager, options));
+        }
+
+        // Q goes into puzzle mode and back out, enter moves on to the next puzzle
+        let next_puzzle = match (key, &attempt) {
+            (Some(KeyCode::Q), None) if !puzzles.is_empty() => Some(puzzle_index),
+            (Some(KeyCode::Enter), Some(_)) => Some((puzzle_index + 1) % puzzles.len()),
+            _ => None,
+        };
+
+        if key == Some(KeyCode::Q) && puzzles.is_empty() {
+            eprintln!("no puzzles found in {}", PUZZLES_PATH);
+        }
+
+        let mut new_game = match key {
+            Some(KeyCode::H) => {
+                huffing = !huffing;
+                Some(board::Manager::with_variant(manager.variant))
+            }
+            Some(KeyCode::B) => ballot::start_position(
+                board::Variant::Standard,
+                ballot::random_ballot(&ballots, &mut thread_rng()),
+            ),
+            Some(KeyCode::N) => Some(board::Manager::with_variant(board::Variant::Standard)),
+            Some(KeyCode::G) => Some(board::Manager::with_variant(board::Variant::Giveaway)),
+            Some(KeyCode::T) => Some(board::Manager::with_variant(board::Variant::Turkish)),
+            Some(KeyCode::I) => Some(board::Manager::with_variant(board::Variant::International)),
+            Some(KeyCode::C) => Some(board::Manager::with_variant(board::Variant::Canadian)),
+            _ => None,
+        };
+
+        if key == Some(KeyCode::Q) && attempt.is_some() {
+            new_game = Some(board::Manager::with_variant(board::Variant::Standard));
+        }
+
+        // whatever the computer was thinking about is no use any more
+        if new_game.is_some()
+            || next_puzzle.is_some()
+            || matches!(key, Some(KeyCode::Key1 | KeyCode::Key2 | KeyCode::P))
+        {
+            thinking.take().iter().for_each(|x| x.stop());
+            pondering.take().into_iter().for_each(|x| x.stop());
+        }
+
+        if let Some(new_manager) = new_game {
+            manager = new_manager;
+            manager.huffing = huffing;
+            review = None;
+            analysing = None; // left to finish on its own
+            attempt = None;
+            active_index = None;
+            active_moves.clear()
+        }
+
+        if let Some(index) = next_puzzle {
+            puzzle_index = index;
 
+            if let Some((started, position)) = puzzle::Attempt::start(puzzles[index].clone()) {
+                manager = position;
+                attempt = Some((started, None));
+                review = None;
+                analysing = None;
                 active_index = None;
                 active_moves.clear()
             }
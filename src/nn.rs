@@ -0,0 +1,518 @@
+use crate::ai::{self, SearchOptions};
+use crate::board::{Manager, Piece, Player, Variant};
+use rand::{seq::SliceRandom, Rng};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+// a small network that scores standard checkers positions. the input is one plane of the
+// 32 dark squares for each of own men, own kings, their men and their kings, always seen
+// from the side to move, then a clipped relu hidden layer and a single output that is
+// trained against the result of the game through tanh
+
+const SQUARES: usize = 32;
+pub const INPUTS: usize = SQUARES * 4;
+pub const DEFAULT_HIDDEN: usize = 64;
+
+// output of the network to search score
+const SCORE_SCALE: f32 = 300f32;
+
+const NETWORK_MAGIC: &[u8; 4] = b"CKNN";
+const DATASET_MAGIC: &[u8; 4] = b"CKDS";
+const SCORED_DATASET_MAGIC: &[u8; 4] = b"CKSS";
+
+// the board from the side to move, 0 for empty, 1 and 2 for own men and kings,
+// 3 and 4 for their men and kings. red to move sees the board turned around
+pub type Position = [u8; SQUARES];
+
+pub fn position(manager: &Manager) -> Position {
+    let side = manager.current_side();
+    let mut squares = [0u8; SQUARES];
+
+    for (index, piece) in manager.board.iter().enumerate() {
+        if piece.is_empty() {
+            continue;
+        }
+
+        let index = match side.is_blue() {
+            true => index,
+            false => manager.board.len() - 1 - index,
+        };
+
+        let own = match piece.match_piece(&side) {
+            true => 0,
+            false => 2,
+        };
+        let king = piece.is_king().unwrap() as u8;
+
+        // two squares per row and only one of them is dark
+        squares[index / 2] = own + king + 1;
+    }
+
+    squares
+}
+
+// a board with the side to move playing blue
+pub fn to_manager(position: &Position) -> Manager {
+    let mut manager = Manager::with_variant(Variant::Standard);
+    let mut board = vec![Piece::Empty; manager.board.len()];
+
+    for (square, x) in position.iter().enumerate() {
+        let row = square / 4;
+        let index = row * 8 + (square % 4) * 2 + (1 - row % 2);
+
+        board[index] = match x {
+            1 => Piece::Blue(false),
+            2 => Piece::Blue(true),
+            3 => Piece::Red(false),
+            4 => Piece::Red(true),
+            _ => Piece::Empty,
+        };
+    }
+
+    manager.set_position(board, Piece::Blue(false));
+    manager
+}
+
+fn features(position: &Position) -> impl Iterator<Item = usize> + '_ {
+    position
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| **x != 0)
+        .map(|(square, x)| (*x as usize - 1) * SQUARES + square)
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Network {
+    pub hidden: usize,
+    weights: Vec<f32>, // INPUTS rows of `hidden` weights
+    biases: Vec<f32>,
+    output_weights: Vec<f32>,
+    output_bias: f32,
+}
+
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Network")
+            .field("hidden", &self.hidden)
+            .finish()
+    }
+}
+
+impl Network {
+    pub fn new<R: Rng>(hidden: usize, rng: &mut R) -> Self {
+        let scale = 1f32 / (INPUTS as f32).sqrt();
+
+        Network {
+            hidden,
+            weights: (0..INPUTS * hidden)
+                .map(|_| rng.gen_range(-scale..scale))
+                .collect(),
+            biases: vec![0.5; hidden],
+            output_weights: (0..hidden).map(|_| rng.gen_range(-0.1..0.1)).collect(),
+            output_bias: 0f32,
+        }
+    }
+
+    // hidden layer before the activation
+    fn accumulate(&self, position: &Position) -> Vec<f32> {
+        let mut hidden = self.biases.clone();
+
+        for feature in features(position) {
+            let row = &self.weights[feature * self.hidden..(feature + 1) * self.hidden];
+            hidden.iter_mut().zip(row).for_each(|(x, w)| *x += w);
+        }
+
+        hidden
+    }
+
+    fn output(&self, hidden: &[f32]) -> f32 {
+        hidden
+            .iter()
+            .zip(&self.output_weights)
+            .map(|(x, w)| x.clamp(0f32, 1f32) * w)
+            .sum::<f32>()
+            + self.output_bias
+    }
+
+    // expected result for the side to move, between -1 and 1
+    pub fn predict(&self, position: &Position) -> f32 {
+        self.output(&self.accumulate(position)).tanh()
+    }
+
+    // score for the side to move in the same units as `ai::evaluate`
+    pub fn evaluate(&self, manager: &Manager) -> i32 {
+        let output = self.output(&self.accumulate(&position(manager)));
+        let limit = ai::WIN_SCORE / 2;
+
+        ((output * SCORE_SCALE) as i32).clamp(-limit, limit)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        file.write_all(NETWORK_MAGIC)?;
+        file.write_all(&(self.hidden as u32).to_le_bytes())?;
+
+        let parameters = self
+            .weights
+            .iter()
+            .chain(&self.biases)
+            .chain(&self.output_weights)
+            .chain(std::iter::once(&self.output_bias));
+
+        for parameter in parameters {
+            file.write_all(&parameter.to_le_bytes())?;
+        }
+
+        file.flush()
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header)?;
+        if &header[..4] != NETWORK_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a network file",
+            ));
+        }
+
+        let hidden = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+        let mut read = |count: usize| -> io::Result<Vec<f32>> {
+            let mut bytes = vec![0u8; count * 4];
+            file.read_exact(&mut bytes)?;
+
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|x| f32::from_le_bytes(x.try_into().unwrap()))
+                .collect())
+        };
+
+        Ok(Network {
+            hidden,
+            weights: read(INPUTS * hidden)?,
+            biases: read(hidden)?,
+            output_weights: read(hidden)?,
+            output_bias: read(1)?[0],
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub struct Sample {
+    pub position: Position,
+    pub result: i8, // 1 if the side to move went on to win, -1 if it lost
+}
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub struct ScoredSample {
+    pub sample: Sample,
+    pub score: i16, // what the engine that played the move thought, for the side to move
+}
+
+// plays a game of the engine against itself, the first `random_plies` moves are picked
+// at random so the games dont all come out the same
+pub fn self_play_game<R: Rng>(
+    options: &SearchOptions,
+    random_plies: usize,
+    rng: &mut R,
+) -> Vec<Sample> {
+    let mut manager = Manager::with_variant(Variant::Standard);
+    manager.players = [Player::User, Player::User];
+
+    let mut positions = Vec::new();
+
+    while !manager.gameover {
+        positions.push((position(&manager), manager.current_side()));
+
+        let next_move = match positions.len() <= random_plies {
+            true => manager.legal_moves().choose(rng).cloned(),
+            false => ai::find_best_move_with(&manager, options),
+        };
+
+        match next_move {
+            Some(next_move) => manager.play_move(next_move),
+            None => break,
+        }
+    }
+
+    positions
+        .into_iter()
+        .map(|(position, side)| Sample {
+            position,
+            result: match manager.winner {
+                Piece::Empty => 0,
+                winner if winner.match_piece(&side) => 1,
+                _ => -1,
+            },
+        })
+        .collect()
+}
+
+// every sample is the 32 squares followed by the result
+pub fn save_samples(path: &str, samples: &[Sample]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    file.write_all(DATASET_MAGIC)?;
+
+    for sample in samples {
+        file.write_all(&sample.position)?;
+        file.write_all(&sample.result.to_le_bytes())?;
+    }
+
+    file.flush()
+}
+
+// the same with the score after the result
+pub fn save_scored_samples(path: &str, samples: &[ScoredSample]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    file.write_all(SCORED_DATASET_MAGIC)?;
+
+    for scored in samples {
+        file.write_all(&scored.sample.position)?;
+        file.write_all(&scored.sample.result.to_le_bytes())?;
+        file.write_all(&scored.score.to_le_bytes())?;
+    }
+
+    file.flush()
+}
+
+pub fn load_scored_samples(path: &str) -> io::Result<Vec<ScoredSample>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+
+    // a plain dataset reads with no scores
+    let size = match &bytes.get(..4) {
+        Some(x) if x == DATASET_MAGIC => SQUARES + 1,
+        Some(x) if x == SCORED_DATASET_MAGIC => SQUARES + 3,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a dataset file",
+            ))
+        }
+    };
+
+    Ok(bytes[4..]
+        .chunks_exact(size)
+        .map(|x| ScoredSample {
+            sample: Sample {
+                position: x[..SQUARES].try_into().unwrap(),
+                result: x[SQUARES] as i8,
+            },
+            score: match size > SQUARES + 1 {
+                true => i16::from_le_bytes([x[SQUARES + 1], x[SQUARES + 2]]),
+                false => 0,
+            },
+        })
+        .collect())
+}
+
+// either kind of dataset, the scores arent used for training
+pub fn load_samples(path: &str) -> io::Result<Vec<Sample>> {
+    Ok(load_scored_samples(path)?
+        .into_iter()
+        .map(|x| x.sample)
+        .collect())
+}
+
+// adam moments for one set of parameters
+#[derive(Clone)]
+struct Moments {
+    mean: Vec<f32>,
+    variance: Vec<f32>,
+}
+
+impl Moments {
+    fn new(length: usize) -> Self {
+        Moments {
+            mean: vec![0f32; length],
+            variance: vec![0f32; length],
+        }
+    }
+
+    fn step(&mut self, parameters: &mut [f32], gradients: &[f32], rate: f32, step: i32) {
+        const BETA1: f32 = 0.9;
+        const BETA2: f32 = 0.999;
+        const EPSILON: f32 = 1e-8;
+
+        let rate = rate * (1f32 - BETA2.powi(step)).sqrt() / (1f32 - BETA1.powi(step));
+
+        for i in 0..parameters.len() {
+            self.mean[i] = BETA1 * self.mean[i] + (1f32 - BETA1) * gradients[i];
+            self.variance[i] =
+                BETA2 * self.variance[i] + (1f32 - BETA2) * gradients[i] * gradients[i];
+            parameters[i] -= rate * self.mean[i] / (self.variance[i].sqrt() + EPSILON);
+        }
+    }
+}
+
+// trains a network with adam on the mean squared error between the prediction and the result
+pub struct Trainer {
+    pub network: Network,
+    pub learning_rate: f32,
+    moments: [Moments; 4],
+    step: i32,
+}
+
+impl Trainer {
+    pub fn new(network: Network, learning_rate: f32) -> Self {
+        let hidden = network.hidden;
+
+        Trainer {
+            network,
+            learning_rate,
+            moments: [
+                Moments::new(INPUTS * hidden),
+                Moments::new(hidden),
+                Moments::new(hidden),
+                Moments::new(1),
+            ],
+            step: 0,
+        }
+    }
+
+    // one pass over the samples in shuffled batches, returns the average loss
+    pub fn epoch<R: Rng>(&mut self, samples: &mut [Sample], batch_size: usize, rng: &mut R) -> f32 {
+        samples.shuffle(rng);
+
+        let mut loss = 0f32;
+
+        for batch in samples.chunks(batch_size.max(1)) {
+            loss += self.train_batch(batch);
+        }
+
+        loss / samples.len().max(1) as f32
+    }
+
+    // summed loss of the batch
+    fn train_batch(&mut self, batch: &[Sample]) -> f32 {
+        let network = &self.network;
+        let hidden_size = network.hidden;
+
+        let mut weights = vec![0f32; INPUTS * hidden_size];
+        let mut biases = vec![0f32; hidden_size];
+        let mut output_weights = vec![0f32; hidden_size];
+        let mut output_bias = 0f32;
+        let mut loss = 0f32;
+
+        for sample in batch {
+            let hidden = network.accumulate(&sample.position);
+            let prediction = network.output(&hidden).tanh();
+            let error = prediction - sample.result as f32;
+
+            loss += error * error;
+
+            // derivative of the loss through the tanh, the 2 is folded into the rate
+            let delta = error * (1f32 - prediction * prediction) / batch.len() as f32;
+            output_bias += delta;
+
+            for j in 0..hidden_size {
+                output_weights[j] += delta * hidden[j].clamp(0f32, 1f32);
+
+                if hidden[j] > 0f32 && hidden[j] < 1f32 {
+                    let delta = delta * network.output_weights[j];
+                    biases[j] += delta;
+
+                    for feature in features(&sample.position) {
+                        weights[feature * hidden_size + j] += delta;
+                    }
+                }
+            }
+        }
+
+        self.step += 1;
+        let (rate, step) = (self.learning_rate, self.step);
+        let network = &mut self.network;
+
+        self.moments[0].step(&mut network.weights, &weights, rate, step);
+        self.moments[1].step(&mut network.biases, &biases, rate, step);
+        self.moments[2].step(&mut network.output_weights, &output_weights, rate, step);
+        self.moments[3].step(
+            std::slice::from_mut(&mut network.output_bias),
+            &[output_bias],
+            rate,
+            step,
+        );
+
+        loss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        load_samples, load_scored_samples, position, save_scored_samples, to_manager, Network,
+        Sample, ScoredSample, Trainer,
+    };
+    use crate::board::{Manager, Piece};
+    use rand::thread_rng;
+
+    #[test]
+    fn check_position_is_from_side_to_move() {
+        let mut manager = Manager::new();
+        let blue = position(&manager);
+
+        // with nothing moved the start looks the same for both sides
+        manager.set_position(manager.board.clone(), Piece::Red(false));
+        assert_eq!(position(&manager), blue);
+
+        assert_eq!(to_manager(&blue).board, Manager::new().board);
+        assert_eq!(blue.iter().filter(|x| **x == 1).count(), 12);
+        assert_eq!(blue.iter().filter(|x| **x == 3).count(), 12);
+    }
+
+    #[test]
+    fn check_training_lowers_loss() {
+        let mut rng = thread_rng();
+        let manager = Manager::new();
+
+        // an extra man for the side to move should be worth a win
+        let mut ahead = manager.clone();
+        ahead.board[1] = Piece::Empty;
+        let mut behind = manager.clone();
+        behind.board[62] = Piece::Empty;
+
+        let mut samples = vec![
+            Sample {
+                position: position(&ahead),
+                result: 1,
+            },
+            Sample {
+                position: position(&behind),
+                result: -1,
+            },
+        ];
+
+        let mut trainer = Trainer::new(Network::new(16, &mut rng), 0.01);
+        let first = trainer.epoch(&mut samples, 2, &mut rng);
+        for _ in 0..200 {
+            trainer.epoch(&mut samples, 2, &mut rng);
+        }
+
+        assert!(trainer.epoch(&mut samples, 2, &mut rng) < first);
+        assert!(trainer.network.evaluate(&ahead) > trainer.network.evaluate(&behind));
+    }
+
+    #[test]
+    fn check_scored_samples() {
+        let samples = vec![ScoredSample {
+            sample: Sample {
+                position: position(&Manager::new()),
+                result: -1,
+            },
+            score: -321,
+        }];
+
+        let path = std::env::temp_dir().join("checkers_scored.data");
+        let path = path.to_str().unwrap();
+        save_scored_samples(path, &samples).unwrap();
+
+        assert_eq!(load_scored_samples(path).unwrap(), samples);
+        assert_eq!(load_samples(path).unwrap(), vec![samples[0].sample]);
+    }
+}
@@ -0,0 +1,523 @@
+use crate::board::{Manager, Move, Piece, Variant};
+use std::mem;
+
+// english checkers numbers the squares starting from the side that moves first,
+// the international style variants start from the other side
+fn numbered_from_first_mover(variant: Variant) -> bool {
+    matches!(variant, Variant::Standard | Variant::Giveaway)
+}
+
+fn squares_per_row(manager: &Manager) -> usize {
+    match manager.variant.is_orthogonal() {
+        true => manager.size,
+        false => manager.size / 2,
+    }
+}
+
+pub fn square_index(manager: &Manager, number: usize) -> Option<usize> {
+    let size = manager.size;
+    let per_row = squares_per_row(manager);
+
+    if number == 0 || number > per_row * size {
+        return None;
+    }
+
+    let row = (number - 1) / per_row;
+    let mut column = (number - 1) % per_row;
+
+    // only the dark squares have numbers, the first row starts on the second square
+    if !manager.variant.is_orthogonal() {
+        column = column * 2 + (1 - row % 2);
+    }
+
+    match numbered_from_first_mover(manager.variant) {
+        true => Some((size - 1 - row) * size + (size - 1 - column)),
+        false => Some(row * size + column),
+    }
+}
+
+pub fn square_number(manager: &Manager, index: usize) -> usize {
+    let size = manager.size;
+
+    let (row, column) = match numbered_from_first_mover(manager.variant) {
+        true => (size - 1 - index / size, size - 1 - index % size),
+        false => (index / size, index % size),
+    };
+
+    let column = match manager.variant.is_orthogonal() {
+        true => column,
+        false => column / 2,
+    };
+
+    row * squares_per_row(manager) + column + 1
+}
+
+// "11-15" for a sliding move and "15x24x31" for a capture, listing every landing square
+pub fn move_to_string(manager: &Manager, played_move: &Move) -> String {
+    if played_move.huff {
+        return format!("h{}", square_number(manager, played_move.start));
+    }
+
+    let separator = match played_move.kills.is_empty() {
+        true => "-",
+        false => "x",
+    };
+
+    std::iter::once(played_move.start)
+        .chain(played_move.through.iter().copied())
+        .chain(std::iter::once(played_move.end))
+        .map(|index| square_number(manager, index).to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+// the moves one after the other from the position, separated by spaces
+pub fn line_to_string(manager: &Manager, moves: &[Move]) -> String {
+    let mut manager = manager.clone();
+    let mut text = Vec::new();
+
+    for played_move in moves {
+        text.push(move_to_string(&manager, played_move));
+        manager.play_move(played_move.clone());
+    }
+
+    text.join(" ")
+}
+
+// finds the legal move written as `text`, captures can leave out the squares in between
+pub fn parse_move(manager: &Manager, text: &str) -> Option<Move> {
+    let text = text.trim();
+
+    if let Some(number) = text.strip_prefix('h') {
+        let index = square_index(manager, number.parse().ok()?)?;
+        return manager
+            .legal_moves()
+            .into_iter()
+            .find(|x| x.huff && x.start == index);
+    }
+
+    let squares = text
+        .split(['-', 'x', ':'])
+        .map(|x| square_index(manager, x.parse().ok()?))
+        .collect::<Option<Vec<usize>>>()?;
+
+    if squares.len() < 2 {
+        return None;
+    }
+
+    let (start, end) = (squares[0], squares[squares.len() - 1]);
+    let through = &squares[1..squares.len() - 1];
+
+    manager.legal_moves().into_iter().find(|x| {
+        !x.huff && x.start == start && x.end == end && (through.is_empty() || x.through == through)
+    })
+}
+
+// the letters the variant usually has for blue and red. black moves first in english
+// checkers and white does in the international style variants
+fn fen_letters(variant: Variant) -> [char; 2] {
+    match numbered_from_first_mover(variant) {
+        true => ['B', 'W'],
+        false => ['W', 'B'],
+    }
+}
+
+// "B:W21,22,K30:B1,2,K5", the side to move and then the squares of each side with K for kings
+pub fn to_fen(manager: &Manager) -> String {
+    let letters = fen_letters(manager.variant);
+    let side = letters[manager.current_side().is_red() as usize];
+
+    let mut text = side.to_string();
+
+    for (letter, side) in letters.iter().zip([Piece::Blue(false), Piece::Red(false)]) {
+        let mut squares: Vec<(usize, bool)> = manager
+            .get_pieces(side)
+            .iter()
+            .map(|(piece, index)| {
+                (
+                    square_number(manager, *index),
+                    piece.is_king() == Some(true),
+                )
+            })
+            .collect();
+        squares.sort();
+
+        let squares: Vec<String> = squares
+            .iter()
+            .map(|(number, king)| match king {
+                true => format!("K{}", number),
+                false => number.to_string(),
+            })
+            .collect();
+
+        text.push_str(&format!(":{}{}", letter, squares.join(",")));
+    }
+
+    text
+}
+
+// also takes ranges like "1-12", as some programs write them
+pub fn from_fen(variant: Variant, text: &str) -> Option<Manager> {
+    let letters = fen_letters(variant);
+    let mut manager = Manager::with_variant(variant);
+    let mut board = vec![Piece::Empty; manager.size * manager.size];
+
+    let mut parts = text.trim().trim_end_matches('.').split(':');
+    let side = match parts.next()?.trim().chars().next()? {
+        x if x == letters[0] => Piece::Blue(false),
+        x if x == letters[1] => Piece::Red(false),
+        _ => return None,
+    };
+
+    for part in parts {
+        let part = part.trim();
+        let side = match part.chars().next()? {
+            x if x == letters[0] => Piece::Blue(false),
+            x if x == letters[1] => Piece::Red(false),
+            _ => return None,
+        };
+
+        for square in part[1..].split(',').filter(|x| !x.trim().is_empty()) {
+            let (king, square) = match square.trim().strip_prefix('K') {
+                Some(square) => (true, square),
+                None => (false, square.trim()),
+            };
+
+            let numbers = match square.split_once('-') {
+                Some((first, last)) => first.parse().ok()?..=last.parse().ok()?,
+                None => square.parse().ok()?..=square.parse().ok()?,
+            };
+
+            for number in numbers {
+                board[square_index(&manager, number)?] = match side {
+                    Piece::Blue(_) => Piece::Blue(king),
+                    _ => Piece::Red(king),
+                };
+            }
+        }
+    }
+
+    manager.set_position(board, side);
+    Some(manager)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub result: String,
+}
+
+impl Game {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // scores are written for the side that moves first, so "1-0" and "2-0" are blue wins.
+    // empty for a draw and none if the game didnt finish
+    pub fn winner(&self) -> Option<Piece> {
+        match self.result.as_str() {
+            "1-0" | "2-0" => Some(Piece::Blue(false)),
+            "0-1" | "0-2" => Some(Piece::Red(false)),
+            "1-1" | "1/2-1/2" => Some(Piece::Empty),
+            _ => None,
+        }
+    }
+
+    // our own variant tag first, then the standard game type numbers
+    pub fn variant(&self) -> Option<Variant> {
+        if let Some(name) = self.tag("Variant") {
+            return Variant::from_name(name);
+        }
+
+        match self
+            .tag("GameType")
+            .map(|x| x.split(',').next().unwrap_or(""))
+        {
+            None | Some("21") => Some(Variant::Standard),
+            Some("20") => Some(Variant::International),
+            Some("27") => Some(Variant::Canadian),
+            Some("30") => Some(Variant::Turkish),
+            Some(_) => None,
+        }
+    }
+
+    // standard checkers played from the start position, what the book and tuner learn from
+    pub fn is_standard_from_start(&self) -> bool {
+        self.tag("FEN").is_none() && self.variant() == Some(Variant::Standard)
+    }
+
+    // the position after every move, none if a move isnt legal
+    pub fn replay(&self) -> Option<Manager> {
+        let mut manager = match self.tag("FEN") {
+            Some(fen) => from_fen(self.variant()?, fen)?,
+            None => Manager::with_variant(self.variant()?),
+        };
+
+        for text in &self.moves {
+            let played_move = parse_move(&manager, text)?;
+            manager.play_move(played_move);
+        }
+
+        Some(manager)
+    }
+}
+
+pub fn game_type(variant: Variant) -> Option<&'static str> {
+    match variant {
+        Variant::Standard => Some("21"),
+        Variant::International => Some("20"),
+        Variant::Canadian => Some("27"),
+        Variant::Turkish => Some("30"),
+        Variant::Giveaway => None,
+    }
+}
+
+pub fn result(manager: &Manager) -> &'static str {
+    match (manager.gameover, manager.winner) {
+        (false, _) => "*",
+        (true, Piece::Blue(_)) => "1-0",
+        (true, Piece::Red(_)) => "0-1",
+        (true, Piece::Empty) => "1/2-1/2",
+    }
+}
+
+// the game as pdn text, `annotations` go after the move with the same index
+pub fn write_game(game: &Game, annotations: &[String]) -> String {
+    let mut text = String::new();
+
+    for (name, value) in &game.tags {
+        text.push_str(&format!("[{} \"{}\"]\n", name, value));
+    }
+
+    let mut tokens = Vec::new();
+
+    for (index, played_move) in game.moves.iter().enumerate() {
+        // the number stays on the same line as its move
+        tokens.push(match index % 2 {
+            0 => format!("{}. {}", index / 2 + 1, played_move),
+            _ => played_move.to_owned(),
+        });
+
+        match annotations.get(index) {
+            Some(annotation) if !annotation.is_empty() => {
+                let last = tokens.len() - 1;
+                tokens[last].push_str(annotation);
+            }
+            _ => {}
+        }
+    }
+
+    tokens.push(game.result.clone());
+
+    // wrapped to lines of about 80 characters
+    let mut line = String::new();
+
+    for token in tokens {
+        if !line.is_empty() && line.len() + token.len() >= 80 {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+
+    text.push_str(&line);
+    text.push('\n');
+
+    text
+}
+
+const RESULTS: [&str; 7] = ["1-0", "2-0", "0-1", "0-2", "1-1", "1/2-1/2", "*"];
+
+// adds a movetext token to the game, true once the result ends it
+fn add_token(game: &mut Game, token: &str) -> bool {
+    if RESULTS.contains(&token) {
+        game.result = token.to_owned();
+        return true;
+    }
+
+    // move numbers like "12." or "12..." can be stuck to the move
+    let token = token.rsplit('.').next().unwrap_or("");
+    let token = token.trim_end_matches(['!', '?']);
+
+    if !token.is_empty() && !token.starts_with('$') {
+        game.moves.push(token.to_owned());
+    }
+
+    false
+}
+
+// reads every game out of a pdn file, comments and variations are skipped
+pub fn read_games(text: &str) -> Vec<Game> {
+    let mut games = Vec::new();
+    let mut game = Game::default();
+    let mut token = String::new();
+    let mut chars = text.chars();
+
+    loop {
+        let c = chars.next();
+
+        let ends_token = c.is_none_or(|x| x.is_whitespace() || "[{(;".contains(x));
+
+        if ends_token && !token.is_empty() && add_token(&mut game, &mem::take(&mut token)) {
+            games.push(mem::take(&mut game));
+        }
+
+        match c {
+            None => break,
+            Some('[') => {
+                let tag: String = chars.by_ref().take_while(|x| *x != ']').collect();
+
+                if !game.moves.is_empty() {
+                    games.push(mem::take(&mut game));
+                }
+
+                if let Some((name, value)) = tag.trim().split_once(' ') {
+                    let value = value.trim().trim_matches('"');
+                    game.tags.push((name.to_owned(), value.to_owned()));
+                }
+            }
+            Some('{') => chars.by_ref().take_while(|x| *x != '}').for_each(drop),
+            Some(';') => chars.by_ref().take_while(|x| *x != '\n').for_each(drop),
+            Some('(') => {
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('(') => depth += 1,
+                        Some(')') => depth -= 1,
+                        None => break,
+                        _ => {}
+                    }
+                }
+            }
+            Some(c) if c.is_whitespace() => {}
+            Some(c) => token.push(c),
+        }
+    }
+
+    if !game.moves.is_empty() {
+        games.push(game);
+    }
+
+    games
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        from_fen, line_to_string, move_to_string, parse_move, read_games, square_index,
+        square_number, to_fen, write_game,
+    };
+    use crate::board::{Manager, Piece, Variant};
+
+    #[test]
+    fn check_square_numbers() {
+        for variant in [Variant::Standard, Variant::Turkish, Variant::Canadian] {
+            let manager = Manager::with_variant(variant);
+
+            for index in 0..manager.board.len() {
+                if manager.variant.is_orthogonal() || (index / manager.size + index) % 2 == 1 {
+                    let number = square_number(&manager, index);
+                    assert_eq!(square_index(&manager, number), Some(index));
+                }
+            }
+        }
+
+        // blue moves first so it starts on 1 to 12
+        let manager = Manager::new();
+        assert!(manager.board[square_index(&manager, 1).unwrap()].is_blue());
+        assert!(manager.board[square_index(&manager, 32).unwrap()].is_red());
+    }
+
+    #[test]
+    fn check_parse_move() {
+        let manager = Manager::new();
+        let opening = parse_move(&manager, "11-15").unwrap();
+
+        assert_eq!(move_to_string(&manager, &opening), "11-15");
+
+        let mut after = manager.clone();
+        after.play_move(opening.clone());
+        let reply = parse_move(&after, "23-19").unwrap();
+        assert_eq!(line_to_string(&manager, &[opening, reply]), "11-15 23-19");
+        assert!(parse_move(&manager, "11-19").is_none());
+        assert!(parse_move(&manager, "33-15").is_none());
+    }
+
+    #[test]
+    fn check_read_games() {
+        let text = r#"
+[Event "Club night"]
+[Result "1-0"]
+1. 11-15 23-19 {the old fourteenth} 2. 8-11 22-17 (2. ... 22-18) 3. 9-13 17x10 1-0
+
+[Event "Casual"]
+1.9-14 22-18! 2.5-9 ; a comment
+24-20 *
+"#;
+        let games = read_games(text);
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("event"), Some("Club night"));
+        assert_eq!(games[0].moves.len(), 6);
+        assert_eq!(games[0].moves[5], "17x10");
+        assert_eq!(games[0].winner(), Some(Piece::Blue(false)));
+        assert_eq!(games[1].moves, vec!["9-14", "22-18", "5-9", "24-20"]);
+        assert_eq!(games[1].winner(), None);
+        assert!(games.iter().all(|x| x.is_standard_from_start()));
+
+        let others = read_games(
+            "[Variant \"giveaway\"]\n1. 11-15 *\n\n[GameType \"20\"]\n1. 32-28 *\n\n\
+             [FEN \"B:B14:W18\"]\n1. 14x23 *",
+        );
+        assert_eq!(others.len(), 3);
+        assert!(others.iter().all(|x| !x.is_standard_from_start()));
+    }
+
+    #[test]
+    fn check_write_game() {
+        let games = read_games("[Event \"Club night\"]\n1. 11-15 23-19 2. 8-11 22-17 1-0");
+        let annotations = vec![String::new(), "?! {22-18 was better}".to_owned()];
+        let text = write_game(&games[0], &annotations);
+
+        assert!(text.contains("1. 11-15 23-19?! {22-18 was better} 2. 8-11"));
+        assert_eq!(read_games(&text), games);
+
+        let manager = games[0].replay().unwrap();
+        assert_eq!(super::result(&manager), "*");
+        assert_eq!(manager.current_side(), Piece::Blue(false));
+    }
+
+    #[test]
+    fn check_fen() {
+        let mut manager = Manager::new();
+        assert_eq!(
+            to_fen(&manager),
+            "B:B1,2,3,4,5,6,7,8,9,10,11,12:W21,22,23,24,25,26,27,28,29,30,31,32"
+        );
+
+        manager.play_move(parse_move(&manager, "11-15").unwrap());
+        let read = from_fen(Variant::Standard, &to_fen(&manager)).unwrap();
+        assert_eq!(read.board, manager.board);
+        assert_eq!(read.current_side(), Piece::Red(false));
+
+        let kings = from_fen(Variant::Standard, "W:W18,K22:B1-3,K9.").unwrap();
+        assert_eq!(to_fen(&kings), "W:B1,2,3,K9:W18,K22");
+
+        for variant in [Variant::International, Variant::Turkish] {
+            let manager = Manager::with_variant(variant);
+            let read = from_fen(variant, &to_fen(&manager)).unwrap();
+            assert_eq!(read.board, manager.board);
+        }
+
+        assert!(from_fen(Variant::Standard, "B:B33").is_none());
+    }
+}
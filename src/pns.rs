@@ -0,0 +1,315 @@
+use crate::board::{Manager, Move, Piece, Player};
+use crate::egdb::{EndgameDb, Outcome};
+use std::sync::Arc;
+
+// proof-number search, grows the game tree towards the moves that look quickest to settle
+// whether the side to move wins. alpha-beta only ever gives a score, this gives a proof.
+// a draw is a proof of two things, no win and no loss, so the tree is searched twice
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub enum Proof {
+    Won, // for the side to move
+    Drawn,
+    Lost,
+    Unknown, // the node budget ran out first
+}
+
+impl Proof {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Proof::Won => "won",
+            Proof::Drawn => "drawn",
+            Proof::Lost => "lost",
+            Proof::Unknown => "unknown",
+        }
+    }
+
+    // the same outcome for the other side
+    pub fn flipped(&self) -> Proof {
+        match self {
+            Proof::Won => Proof::Lost,
+            Proof::Lost => Proof::Won,
+            x => *x,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProofOptions {
+    pub max_nodes: usize,             // over both searches
+    pub egdb: Option<Arc<EndgameDb>>, // settles positions with few pieces without a search
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProofResult {
+    pub outcome: Proof,
+    pub line: Vec<Move>, // the winners moves and a reply from the other side, to the end
+    pub nodes: usize,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Target {
+    Win,
+    NotLose,
+}
+
+const INFINITY: u32 = u32::MAX;
+
+struct Node {
+    played: Option<Move>,
+    parent: usize,
+    children: Vec<usize>,
+    proof: u32,     // leaves that still have to be proven to prove this
+    disproof: u32,  // and to disprove it
+    attacker: bool, // the side trying to prove the target is to move, any child will do
+}
+
+struct Solver<'a> {
+    options: &'a ProofOptions,
+    side: Piece, // the attacker
+    target: Target,
+    nodes: Vec<Node>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(options: &'a ProofOptions, manager: &Manager, target: Target) -> Self {
+        let mut solver = Solver {
+            options,
+            side: manager.current_side(),
+            target,
+            nodes: Vec::new(),
+        };
+
+        let (proof, disproof) = solver.numbers(manager);
+        solver.nodes.push(Node {
+            played: None,
+            parent: 0,
+            children: Vec::new(),
+            proof,
+            disproof,
+            attacker: true,
+        });
+
+        solver
+    }
+
+    // the winner if the game is over or the database knows the position, empty for a draw
+    fn known(&self, manager: &Manager) -> Option<Piece> {
+        if manager.gameover {
+            return Some(manager.winner);
+        }
+
+        let side = manager.current_side();
+
+        match self.options.egdb.as_ref()?.probe(manager)? {
+            Outcome::Win(_) => Some(side),
+            Outcome::Loss(_) => Some(side.opposite()),
+            Outcome::Draw => Some(Piece::Empty),
+        }
+    }
+
+    // a settled position is 0 and infinity, otherwise the side to move is helped by having
+    // more moves to choose from
+    fn numbers(&self, manager: &Manager) -> (u32, u32) {
+        if let Some(winner) = self.known(manager) {
+            let proven = match self.target {
+                Target::Win => winner.match_piece(&self.side),
+                Target::NotLose => !winner.match_piece(&self.side.opposite()),
+            };
+
+            return match proven {
+                true => (0, INFINITY),
+                false => (INFINITY, 0),
+            };
+        }
+
+        let moves = manager.legal_moves().len() as u32;
+
+        match manager.current_side().match_piece(&self.side) {
+            true => (1, moves),
+            false => (moves, 1),
+        }
+    }
+
+    fn expand(&mut self, index: usize, manager: &mut Manager) {
+        for possible_move in manager.legal_moves() {
+            manager.play_move(possible_move.clone());
+            let (proof, disproof) = self.numbers(manager);
+            let attacker = manager.current_side().match_piece(&self.side);
+            manager.undo_move();
+
+            let child = self.nodes.len();
+            self.nodes[index].children.push(child);
+            self.nodes.push(Node {
+                played: Some(possible_move),
+                parent: index,
+                children: Vec::new(),
+                proof,
+                disproof,
+                attacker,
+            });
+        }
+    }
+
+    fn update(&mut self, index: usize) {
+        let node = &self.nodes[index];
+        let children = node.children.iter().map(|x| &self.nodes[*x]);
+
+        let min_proof = children.clone().map(|x| x.proof).min().unwrap_or(INFINITY);
+        let min_disproof = children
+            .clone()
+            .map(|x| x.disproof)
+            .min()
+            .unwrap_or(INFINITY);
+        let sum_proof = children
+            .clone()
+            .fold(0, |sum: u32, x| sum.saturating_add(x.proof));
+        let sum_disproof = children.fold(0, |sum: u32, x| sum.saturating_add(x.disproof));
+
+        let (proof, disproof) = match node.attacker {
+            true => (min_proof, sum_disproof),
+            false => (sum_proof, min_disproof),
+        };
+
+        self.nodes[index].proof = proof;
+        self.nodes[index].disproof = disproof;
+    }
+
+    fn settled(&self) -> bool {
+        self.nodes[0].proof == 0 || self.nodes[0].disproof == 0
+    }
+
+    fn run(&mut self, root: &Manager, budget: usize) {
+        let mut manager = root.clone();
+
+        while !self.settled() && self.nodes.len() < budget {
+            // down to the most proving node, the child the side to move would pick
+            let mut index = 0;
+
+            while !self.nodes[index].children.is_empty() {
+                let node = &self.nodes[index];
+                index = *node
+                    .children
+                    .iter()
+                    .min_by_key(|x| match node.attacker {
+                        true => self.nodes[**x].proof,
+                        false => self.nodes[**x].disproof,
+                    })
+                    .unwrap();
+
+                manager.play_move(self.nodes[index].played.clone().unwrap());
+            }
+
+            self.expand(index, &mut manager);
+
+            // and back up to the root
+            loop {
+                self.update(index);
+
+                if index == 0 {
+                    break;
+                }
+
+                manager.undo_move();
+                index = self.nodes[index].parent;
+            }
+        }
+    }
+
+    // follows the settled children, the side that has to find a move takes one that
+    // works and for the other side every move does
+    fn line(&self) -> Vec<Move> {
+        let proven = self.nodes[0].proof == 0;
+        let mut line = Vec::new();
+        let mut index = 0;
+
+        while let Some(next) = self.nodes[index].children.iter().find(|x| match proven {
+            true => self.nodes[**x].proof == 0,
+            false => self.nodes[**x].disproof == 0,
+        }) {
+            index = *next;
+            line.push(self.nodes[index].played.clone().unwrap());
+        }
+
+        line
+    }
+}
+
+pub fn prove(manager: &Manager, options: &ProofOptions) -> ProofResult {
+    let mut manager = manager.clone();
+    manager.players = [Player::User, Player::User];
+
+    let mut win = Solver::new(options, &manager, Target::Win);
+    win.run(&manager, options.max_nodes);
+
+    let unknown = |nodes| ProofResult {
+        outcome: Proof::Unknown,
+        line: Vec::new(),
+        nodes,
+    };
+
+    if !win.settled() {
+        return unknown(win.nodes.len());
+    }
+
+    if win.nodes[0].proof == 0 {
+        return ProofResult {
+            outcome: Proof::Won,
+            line: win.line(),
+            nodes: win.nodes.len(),
+        };
+    }
+
+    // not a win, so either a draw or a loss
+    let mut not_lose = Solver::new(options, &manager, Target::NotLose);
+    not_lose.run(&manager, options.max_nodes.saturating_sub(win.nodes.len()));
+
+    let nodes = win.nodes.len() + not_lose.nodes.len();
+
+    if !not_lose.settled() {
+        return unknown(nodes);
+    }
+
+    ProofResult {
+        outcome: match not_lose.nodes[0].proof == 0 {
+            true => Proof::Drawn,
+            false => Proof::Lost,
+        },
+        line: not_lose.line(),
+        nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prove, Proof, ProofOptions};
+    use crate::board::{Manager, Variant};
+    use crate::pdn;
+
+    #[test]
+    fn check_prove() {
+        let options = ProofOptions {
+            max_nodes: 100_000,
+            ..Default::default()
+        };
+
+        // blue takes the last red man
+        let manager = pdn::from_fen(Variant::Standard, "B:B14:W18").unwrap();
+        let result = prove(&manager, &options);
+        assert_eq!(result.outcome, Proof::Won);
+        assert_eq!(pdn::move_to_string(&manager, &result.line[0]), "14x23");
+
+        // red has to step into a capture whichever way it goes
+        let manager = pdn::from_fen(Variant::Standard, "W:B14,15:W23").unwrap();
+        let result = prove(&manager, &options);
+        assert_eq!(result.outcome, Proof::Lost);
+        assert_eq!(result.line.len(), 2);
+
+        // far too much to settle
+        let options = ProofOptions {
+            max_nodes: 1000,
+            ..Default::default()
+        };
+        assert_eq!(prove(&Manager::new(), &options).outcome, Proof::Unknown);
+    }
+}
@@ -0,0 +1,656 @@
+use crate::ai::{self, BackgroundSearch, SearchOptions};
+use crate::board::{Manager, Move, Piece, Variant};
+use crate::pdn::{self, Game};
+use crate::pns::{self, Proof, ProofOptions};
+use std::fs;
+use std::io;
+
+// tactics found in played games, positions where a single move wins material or is the
+// only one that doesnt lose it. a puzzle file is pdn, every puzzle a game with the
+// position in a FEN tag and the solution as its moves
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub enum Goal {
+    Win,  // the solution comes out at least `margin` ahead
+    Save, // every other move loses at least `margin`
+}
+
+impl Goal {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Goal::Win => "win",
+            Goal::Save => "save",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    pub variant: Variant,
+    pub fen: String,
+    pub goal: Goal,
+    pub solution: Vec<String>, // starts and ends with a move of the side solving it
+    pub score: i32,
+    pub margin: i32, // the one it was found with, another move has to reach the goal by it
+    pub proven: bool, // the proof search settled that the goal can be reached
+}
+
+impl Puzzle {
+    pub fn start(&self) -> Option<Manager> {
+        pdn::from_fen(self.variant, &self.fen)
+    }
+
+    pub fn to_game(&self) -> Game {
+        let mut tags = vec![
+            ("Event".to_owned(), "Puzzle".to_owned()),
+            ("Variant".to_owned(), self.variant.name().to_owned()),
+        ];
+
+        if let Some(game_type) = pdn::game_type(self.variant) {
+            tags.push(("GameType".to_owned(), game_type.to_owned()));
+        }
+
+        tags.push(("FEN".to_owned(), self.fen.clone()));
+        tags.push(("Goal".to_owned(), self.goal.name().to_owned()));
+        tags.push(("Score".to_owned(), self.score.to_string()));
+        tags.push(("Margin".to_owned(), self.margin.to_string()));
+
+        if self.proven {
+            tags.push(("Proven".to_owned(), "yes".to_owned()));
+        }
+
+        Game {
+            tags,
+            moves: self.solution.clone(),
+            result: "*".to_owned(),
+        }
+    }
+
+    pub fn from_game(game: &Game) -> Option<Self> {
+        let goal = match game.tag("Goal") {
+            Some("save") => Goal::Save,
+            _ => Goal::Win,
+        };
+
+        let puzzle = Puzzle {
+            variant: game.variant()?,
+            fen: game.tag("FEN")?.to_owned(),
+            goal,
+            solution: game.moves.clone(),
+            score: game.tag("Score").and_then(|x| x.parse().ok()).unwrap_or(0),
+            margin: game
+                .tag("Margin")
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(PuzzleOptions::default().margin),
+            proven: game.tag("Proven") == Some("yes"),
+        };
+
+        // the solution has to be playable from the position
+        game.replay()?;
+
+        Some(puzzle)
+    }
+}
+
+pub fn read_puzzles(text: &str) -> Vec<Puzzle> {
+    pdn::read_games(text)
+        .iter()
+        .filter_map(Puzzle::from_game)
+        .collect()
+}
+
+pub fn write_puzzles(puzzles: &[Puzzle]) -> String {
+    puzzles
+        .iter()
+        .map(|x| pdn::write_game(&x.to_game(), &[]))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[derive(Clone, Debug)]
+pub struct PuzzleOptions {
+    pub scan_depth: u8,     // every position of a game is searched this deep
+    pub verify_depth: u8,   // and the candidates again this deep
+    pub margin: i32,        // how much better the solution has to be than any other move
+    pub max_plies: usize,   // longest solution kept
+    pub proof_nodes: usize, // budget to prove the goal with, 0 to leave it unproven
+}
+
+impl Default for PuzzleOptions {
+    fn default() -> Self {
+        PuzzleOptions {
+            scan_depth: 6,
+            verify_depth: 10,
+            margin: 150,
+            max_plies: 9,
+            proof_nodes: 20_000,
+        }
+    }
+}
+
+// once someone solving a puzzle leaves its line, their moves and the defence are searched
+// this deep
+const ATTEMPT_DEPTH: u8 = 6;
+
+// whether a move scoring `score` reaches the goal
+fn reaches(goal: Goal, score: i32, margin: i32) -> bool {
+    match goal {
+        Goal::Win => score >= margin,
+        Goal::Save => score > -margin / 2,
+    }
+}
+
+// the goal if the best two moves are far enough apart
+fn goal(best: i32, second: i32, margin: i32) -> Option<Goal> {
+    if best - second < margin {
+        return None;
+    }
+
+    if reaches(Goal::Win, best, margin) && second <= margin / 2 {
+        Some(Goal::Win)
+    } else if reaches(Goal::Save, best, margin) && second <= -margin {
+        Some(Goal::Save)
+    } else {
+        None
+    }
+}
+
+fn best_two(manager: &Manager, depth: u8, margin: i32) -> Option<(Vec<Move>, i32, Goal)> {
+    let options = SearchOptions {
+        depth,
+        multi_pv: 2,
+        ..Default::default()
+    };
+    let mut lines = ai::search(manager, &options)?.lines;
+
+    if lines.len() < 2 {
+        return None;
+    }
+
+    let goal = goal(lines[0].score, lines[1].score, margin)?;
+    let best = lines.swap_remove(0);
+
+    Some((best.moves, best.score, goal))
+}
+
+// the principal variation up to the last capture of the solving side. a win has to take
+// more than it gives back and be a shot or a multi-jump, not just a piece left hanging
+fn solution(line: &[Move], goal: Goal, max_plies: usize) -> Option<Vec<Move>> {
+    let line = &line[..line.len().min(max_plies)];
+
+    if line.is_empty() {
+        return None;
+    }
+
+    // the solving side has the even plies
+    let end = (0..line.len())
+        .step_by(2)
+        .rev()
+        .find(|x| !line[*x].kills.is_empty())
+        .map_or(1, |x| x + 1);
+
+    let gained: i32 = line
+        .iter()
+        .take(end + 1)
+        .enumerate()
+        .map(|(ply, x)| match ply % 2 {
+            0 => x.kills.len() as i32,
+            _ => -(x.kills.len() as i32),
+        })
+        .sum();
+
+    let solution = &line[..end];
+    let combination = solution.len() >= 3 || solution[0].kills.len() >= 2;
+
+    match goal {
+        Goal::Win if gained < 1 || !combination => None,
+        _ => Some(solution.to_vec()),
+    }
+}
+
+// every position the game went through that makes a puzzle, the ones within a solution
+// already found are skipped
+pub fn find_puzzles(manager: &Manager, options: &PuzzleOptions) -> Vec<Puzzle> {
+    let mut puzzles = Vec::new();
+
+    // a skipped capture could be huffed, which FEN has no way to say
+    if manager.huffing {
+        return puzzles;
+    }
+
+    let mut position = manager.initial();
+    let mut skip = 0;
+
+    for played in manager.played_moves() {
+        if skip > 0 {
+            skip -= 1;
+        } else if let Some(puzzle) = examine(&position, options) {
+            skip = puzzle.solution.len();
+            puzzles.push(puzzle);
+        }
+
+        position.play_move(played);
+    }
+
+    puzzles
+}
+
+// a win has to be proven won, saving the position is enough for it to be no loss
+fn proven(manager: &Manager, goal: Goal, proof_nodes: usize) -> bool {
+    if proof_nodes == 0 {
+        return false;
+    }
+
+    let options = ProofOptions {
+        max_nodes: proof_nodes,
+        egdb: None,
+    };
+
+    matches!(
+        (goal, pns::prove(manager, &options).outcome),
+        (_, Proof::Won) | (Goal::Save, Proof::Drawn)
+    )
+}
+
+// a quick search to find candidates and a deep one to make sure the solution is unique
+pub fn examine(manager: &Manager, options: &PuzzleOptions) -> Option<Puzzle> {
+    if manager.gameover || manager.huffing || manager.legal_moves().len() < 2 {
+        return None;
+    }
+
+    best_two(manager, options.scan_depth, options.margin)?;
+    let (line, score, goal) = best_two(manager, options.verify_depth, options.margin)?;
+    let solution = solution(&line, goal, options.max_plies)?;
+
+    let mut position = manager.clone();
+    let solution = solution
+        .into_iter()
+        .map(|x| {
+            let text = pdn::move_to_string(&position, &x);
+            position.play_move(x);
+            text
+        })
+        .collect();
+
+    Some(Puzzle {
+        variant: manager.variant,
+        fen: pdn::to_fen(manager),
+        goal,
+        solution,
+        score,
+        margin: options.margin,
+        proven: proven(manager, goal, options.proof_nodes),
+    })
+}
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub enum Step {
+    Correct, // and the defending reply has been played
+    Wrong,   // the move isnt played, it can be tried again
+    Solved,
+    Thinking, // the move is being checked or the defence searched, `poll` says how it went
+}
+
+// the searches an attempt runs off the board it is played on
+enum Pending {
+    Check(Move, BackgroundSearch), // whether a move off the solution still reaches the goal
+    Defence(BackgroundSearch),
+    Expected(BackgroundSearch), // the best move once the solution has been left
+}
+
+// someone working through a puzzle on a board of their own
+pub struct Attempt {
+    pub puzzle: Puzzle,
+    pub failed: bool, // a wrong move was tried at some point
+    ply: usize,
+    on_line: bool,          // every move so far was the one in the solution
+    expected: Option<Move>, // the best move for the side solving it
+    pending: Option<Pending>,
+}
+
+impl Attempt {
+    pub fn start(puzzle: Puzzle) -> Option<(Attempt, Manager)> {
+        let manager = puzzle.start()?;
+        let mut attempt = Attempt {
+            puzzle,
+            failed: false,
+            ply: 0,
+            on_line: true,
+            expected: None,
+            pending: None,
+        };
+        attempt.find_expected(&manager);
+
+        Some((attempt, manager))
+    }
+
+    pub fn solved(&self) -> bool {
+        self.ply >= self.puzzle.solution.len()
+    }
+
+    // the move that should be played next, the solutions own until it has been left
+    pub fn expected(&self) -> Option<Move> {
+        self.expected.clone()
+    }
+
+    fn search(manager: &Manager) -> BackgroundSearch {
+        let options = SearchOptions {
+            depth: ATTEMPT_DEPTH,
+            ..Default::default()
+        };
+
+        BackgroundSearch::start(manager, options)
+    }
+
+    fn stored(&self, manager: &Manager) -> Option<Move> {
+        let stored = self.puzzle.solution.get(self.ply).filter(|_| self.on_line);
+
+        stored.and_then(|x| pdn::parse_move(manager, x))
+    }
+
+    fn find_expected(&mut self, manager: &Manager) {
+        self.expected = self.stored(manager);
+
+        if self.expected.is_none() && !manager.gameover {
+            self.pending = Some(Pending::Expected(Self::search(manager)));
+        }
+    }
+
+    // plays the move if its the solutions or reaches the goal some other way, then the
+    // defence, from the solution for as long as it is followed and searched after that.
+    // any other move is searched in the background and the step comes from `poll`
+    pub fn play(&mut self, manager: &mut Manager, played: Move) -> Step {
+        if self.solved() {
+            return Step::Solved;
+        }
+
+        match self.pending.take() {
+            Some(Pending::Expected(search)) => search.stop(),
+            Some(pending) => {
+                self.pending = Some(pending);
+                return Step::Thinking;
+            }
+            None => {}
+        }
+
+        if self.expected.as_ref() == Some(&played) {
+            return self.accept(manager, played);
+        }
+
+        let side = manager.current_side();
+        let mut after = manager.clone();
+        after.play_move(played.clone());
+
+        let score = match after.gameover {
+            true if after.winner.match_piece(&side) => i32::MAX,
+            true if after.winner == Piece::Empty => 0,
+            true => i32::MIN,
+            false => {
+                self.pending = Some(Pending::Check(played, Self::search(&after)));
+                return Step::Thinking;
+            }
+        };
+
+        self.judge(manager, played, score)
+    }
+
+    // the step a move has come to once its search is done, none while it isnt
+    pub fn poll(&mut self, manager: &mut Manager) -> Option<Step> {
+        let finished = match &self.pending {
+            Some(
+                Pending::Check(_, search) | Pending::Defence(search) | Pending::Expected(search),
+            ) => search.is_finished(),
+            None => false,
+        };
+
+        match finished {
+            true => self.wait(manager),
+            false => None,
+        }
+    }
+
+    // the same without giving up on a search thats still going
+    pub fn wait(&mut self, manager: &mut Manager) -> Option<Step> {
+        match self.pending.take()? {
+            Pending::Check(played, search) => {
+                let score = search.wait().map_or(0, |x| -x.score);
+                Some(self.judge(manager, played, score))
+            }
+            Pending::Defence(search) => {
+                match search.wait() {
+                    Some(reply) => {
+                        manager.play_move(reply.best_move);
+                        self.ply += 1;
+                    }
+                    None => self.ply = self.puzzle.solution.len(),
+                }
+                Some(self.defended(manager))
+            }
+            Pending::Expected(search) => {
+                self.expected = search.wait().map(|x| x.best_move);
+                None
+            }
+        }
+    }
+
+    // another move is as good as the solutions if it still reaches the goal
+    fn judge(&mut self, manager: &mut Manager, played: Move, score: i32) -> Step {
+        match reaches(self.puzzle.goal, score, self.puzzle.margin) {
+            true => self.accept(manager, played),
+            false => {
+                self.failed = true;
+                Step::Wrong
+            }
+        }
+    }
+
+    fn accept(&mut self, manager: &mut Manager, played: Move) -> Step {
+        self.on_line &= self.expected.as_ref() == Some(&played);
+        self.expected = None;
+        manager.play_move(played);
+        self.ply += 1;
+
+        if self.solved() || manager.gameover {
+            self.ply = self.puzzle.solution.len();
+            return Step::Solved;
+        }
+
+        match self.stored(manager) {
+            Some(reply) => {
+                manager.play_move(reply);
+                self.ply += 1;
+                self.defended(manager)
+            }
+            None => {
+                self.pending = Some(Pending::Defence(Self::search(manager)));
+                Step::Thinking
+            }
+        }
+    }
+
+    fn defended(&mut self, manager: &Manager) -> Step {
+        if self.solved() || manager.gameover {
+            self.ply = self.puzzle.solution.len();
+            return Step::Solved;
+        }
+
+        self.find_expected(manager);
+        Step::Correct
+    }
+}
+
+// kept between sessions, a puzzle counts as solved only without a wrong move on the way
+#[derive(Copy, Clone, Eq, Debug, Default, PartialEq)]
+pub struct PuzzleStats {
+    pub solved: u32,
+    pub failed: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl PuzzleStats {
+    pub fn record(&mut self, solved: bool) {
+        match solved {
+            true => {
+                self.solved += 1;
+                self.streak += 1;
+                self.best_streak = self.best_streak.max(self.streak);
+            }
+            false => {
+                self.failed += 1;
+                self.streak = 0;
+            }
+        }
+    }
+
+    // one `name value` pair per line like the weights
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text = format!(
+            "solved {}\nfailed {}\nstreak {}\nbest_streak {}\n",
+            self.solved, self.failed, self.streak, self.best_streak
+        );
+
+        fs::write(path, text)
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut stats = PuzzleStats::default();
+
+        for line in fs::read_to_string(path)?.lines() {
+            let (name, value) = match line.trim().split_once(' ') {
+                Some((name, value)) => (name, value.trim().parse().unwrap_or(0)),
+                None => continue,
+            };
+
+            match name {
+                "solved" => stats.solved = value,
+                "failed" => stats.failed = value,
+                "streak" => stats.streak = value,
+                "best_streak" => stats.best_streak = value,
+                _ => {}
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        examine, read_puzzles, write_puzzles, Attempt, Goal, Puzzle, PuzzleOptions, PuzzleStats,
+        Step,
+    };
+    use crate::ai::WIN_SCORE;
+    use crate::board::{Manager, Piece, Variant};
+    use crate::pdn;
+
+    #[test]
+    fn check_examine() {
+        let options = PuzzleOptions {
+            scan_depth: 4,
+            verify_depth: 6,
+            ..Default::default()
+        };
+
+        // the start position has nothing to find
+        assert!(examine(&Manager::new(), &options).is_none());
+
+        // giving a man on 17 to jump three back
+        let position =
+            pdn::from_fen(Variant::Standard, "W:B5,7,8,13,15,26:W14,22,25,27,29,32").unwrap();
+        let puzzle = examine(&position, &options).unwrap();
+
+        assert_eq!(puzzle.goal, Goal::Win);
+        assert_eq!(puzzle.solution[0], "22-17");
+
+        let read = read_puzzles(&write_puzzles(std::slice::from_ref(&puzzle)));
+        assert_eq!(read, vec![puzzle]);
+
+        // no room for a solution at all
+        let options = PuzzleOptions {
+            max_plies: 0,
+            ..options
+        };
+        assert!(examine(&position, &options).is_none());
+    }
+
+    #[test]
+    fn check_attempt() {
+        let puzzle = Puzzle {
+            variant: Variant::Standard,
+            fen: "W:B5,7,8,13,15,26:W14,22,25,27,29,32".to_owned(),
+            goal: Goal::Win,
+            solution: vec![
+                "22-17".to_owned(),
+                "13x22".to_owned(),
+                "25x18x11x2".to_owned(),
+            ],
+            score: 246,
+            margin: 150,
+            proven: false,
+        };
+
+        let (mut attempt, mut manager) = Attempt::start(puzzle).unwrap();
+        let wrong = pdn::parse_move(&manager, "27-23").unwrap();
+        let right = attempt.expected().unwrap();
+
+        // a move off the solution is checked by a search, any other waits for it
+        assert_eq!(attempt.play(&mut manager, wrong.clone()), Step::Thinking);
+        assert_eq!(attempt.play(&mut manager, right.clone()), Step::Thinking);
+        assert_eq!(attempt.wait(&mut manager), Some(Step::Wrong));
+        assert_eq!(attempt.play(&mut manager, right), Step::Correct);
+
+        // the reply is already on the board and red, white in the FEN, is to move again
+        assert!(manager.current_side().is_red());
+        let last = attempt.expected().unwrap();
+        assert_eq!(attempt.play(&mut manager, last), Step::Solved);
+        assert!(attempt.failed);
+
+        // 22x15x8 wins as well as the 23x14x7 of the solution, after it the defence of the
+        // solution isnt even legal so it has to be searched
+        let puzzle = Puzzle {
+            variant: Variant::Standard,
+            fen: "W:B10,11,18:W22,23,26,K1".to_owned(),
+            goal: Goal::Win,
+            solution: vec!["23x14x7".to_owned(), "11-16".to_owned(), "22-18".to_owned()],
+            score: 420,
+            margin: 150,
+            proven: false,
+        };
+
+        let (mut attempt, mut manager) = Attempt::start(puzzle).unwrap();
+        let other = pdn::parse_move(&manager, "22x15x8").unwrap();
+
+        assert_eq!(attempt.play(&mut manager, other), Step::Thinking);
+        assert_eq!(attempt.wait(&mut manager), Some(Step::Thinking));
+        assert_eq!(attempt.wait(&mut manager), Some(Step::Correct));
+        assert!(manager.current_side().is_red());
+        assert_eq!(manager.get_pieces(Piece::Blue(false)).len(), 1);
+
+        assert_eq!(attempt.wait(&mut manager), None);
+        let last = attempt.expected().unwrap();
+        assert_eq!(attempt.play(&mut manager, last), Step::Solved);
+        assert!(!attempt.failed);
+
+        // made with a margin no search score reaches the other win falls short
+        let mut puzzle = attempt.puzzle.clone();
+        puzzle.margin = WIN_SCORE + 1;
+        let puzzle = read_puzzles(&write_puzzles(&[puzzle])).remove(0);
+
+        let (mut attempt, mut manager) = Attempt::start(puzzle).unwrap();
+        let other = pdn::parse_move(&manager, "22x15x8").unwrap();
+
+        assert_eq!(attempt.play(&mut manager, other), Step::Thinking);
+        assert_eq!(attempt.wait(&mut manager), Some(Step::Wrong));
+
+        let mut stats = PuzzleStats::default();
+        stats.record(true);
+        stats.record(true);
+        stats.record(false);
+        assert_eq!(
+            (stats.solved, stats.failed, stats.streak, stats.best_streak),
+            (2, 1, 0, 2)
+        );
+    }
+}
@@ -1,10 +1,11 @@
-use crate::board::Piece;
+use checkers_ai::board::Piece;
 use macroquad::prelude::*;
 use std::collections::HashMap;
 
 pub struct Resources {
     pub background: Texture2D,
     pub pieces: HashMap<Piece, Texture2D>,
+    #[allow(dead_code)] // todo: draw the banners once they are loaded
     pub banners: HashMap<Piece, Texture2D>,
 }
 
@@ -0,0 +1,153 @@
+use crate::board::Move;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// transposition table, one entry per slot and a deeper search keeps its slot unless the
+// position is a different one. the table is shared between search threads without a lock,
+// every slot keeps the entry packed into one word and the key xored with it in another,
+// so an entry torn by two threads writing at once just doesnt match its key any more
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower, // the score is at least this, the search failed high
+    Upper, // the score is at most this, no move got above alpha
+}
+
+#[derive(Copy, Clone, Eq, Debug, PartialEq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    pub best: Option<(u8, u8)>, // start and end of the best move
+}
+
+// the start and end squares are enough to tell the moves apart nearly always
+pub fn move_key(stored_move: &Move) -> (u8, u8) {
+    (stored_move.start as u8, stored_move.end as u8)
+}
+
+// score in the low 32 bits, then depth, bound, best move and a bit so no entry is 0
+const VALID: u64 = 1 << 63;
+
+fn pack(entry: &Entry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let best = match entry.best {
+        Some((start, end)) => 1 | (start as u64) << 1 | (end as u64) << 9,
+        None => 0,
+    };
+
+    entry.score as u32 as u64 | (entry.depth as u64) << 32 | bound << 40 | best << 42 | VALID
+}
+
+fn unpack(key: u64, data: u64) -> Entry {
+    let best = data >> 42;
+
+    Entry {
+        key,
+        depth: (data >> 32) as u8,
+        score: data as u32 as i32,
+        bound: match (data >> 40) & 3 {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        },
+        best: match best & 1 {
+            1 => Some(((best >> 1) as u8, (best >> 9) as u8)),
+            _ => None,
+        },
+    }
+}
+
+pub struct TranspositionTable {
+    checks: Vec<AtomicU64>, // key ^ data
+    data: Vec<AtomicU64>,
+}
+
+impl TranspositionTable {
+    // the size is rounded up to a power of two
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1).next_power_of_two();
+
+        TranspositionTable {
+            checks: (0..size).map(|_| AtomicU64::new(0)).collect(),
+            data: (0..size).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        key as usize & (self.data.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = self.slot(key);
+        let data = self.data[slot].load(Ordering::Relaxed);
+        let check = self.checks[slot].load(Ordering::Relaxed);
+
+        match data != 0 && check ^ data == key {
+            true => Some(unpack(key, data)),
+            false => None,
+        }
+    }
+
+    pub fn store(&self, entry: Entry) {
+        if let Some(existing) = self.probe(entry.key) {
+            if existing.depth > entry.depth {
+                return;
+            }
+        }
+
+        let slot = self.slot(entry.key);
+        let data = pack(&entry);
+
+        self.checks[slot].store(entry.key ^ data, Ordering::Relaxed);
+        self.data[slot].store(data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in 0..self.data.len() {
+            self.checks[slot].store(0, Ordering::Relaxed);
+            self.data[slot].store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, Entry, TranspositionTable};
+
+    #[test]
+    fn check_store_and_probe() {
+        let table = TranspositionTable::new(1000);
+        let entry = Entry {
+            key: 7,
+            depth: 4,
+            score: -30,
+            bound: Bound::Upper,
+            best: Some((42, 33)),
+        };
+
+        table.store(entry);
+        assert_eq!(table.probe(7), Some(entry));
+        assert_eq!(table.probe(7 + 1024), None);
+
+        // a shallower search of the same position doesnt replace it
+        table.store(Entry { depth: 2, ..entry });
+        assert_eq!(table.probe(7).unwrap().depth, 4);
+
+        table.store(Entry {
+            key: 7 + 1024,
+            best: None,
+            ..entry
+        });
+        assert_eq!(table.probe(7), None);
+        assert_eq!(table.probe(7 + 1024).unwrap().best, None);
+
+        table.clear();
+        assert_eq!(table.probe(7 + 1024), None);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::ai::{eval_terms, Weights, TERMS};
+use crate::board::{Manager, Piece, Variant};
+use crate::nn::{self, Sample};
+use crate::pdn::{self, Game};
+
+// texel tuning, the weights are fitted so that a sigmoid of the evaluation predicts the
+// results of the games the positions came from
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TuningPosition {
+    pub terms: [i32; TERMS], // side to move minus the other side
+    pub result: f64,         // 1 if the side to move won, 0 if it lost
+}
+
+impl TuningPosition {
+    // only quiet positions, the evaluation isnt meant to be right in the middle of a capture
+    fn new(manager: &Manager, result: f64) -> Option<Self> {
+        if manager.legal_moves().iter().any(|x| !x.kills.is_empty()) {
+            return None;
+        }
+
+        let [own, other] = eval_terms(manager);
+        let mut terms = [0; TERMS];
+        for x in 0..TERMS {
+            terms[x] = own[x] - other[x];
+        }
+
+        Some(TuningPosition { terms, result })
+    }
+
+    fn evaluate(&self, weights: &Weights) -> i32 {
+        (0..TERMS).map(|x| weights.0[x] * self.terms[x]).sum()
+    }
+}
+
+// positions from standard checkers games, leaving out the opening moves
+pub fn positions_from_games(games: &[Game], skip_plies: usize) -> Vec<TuningPosition> {
+    let mut positions = Vec::new();
+
+    for game in games {
+        if !game.is_standard_from_start() {
+            continue;
+        }
+
+        let winner = match game.winner() {
+            Some(winner) => winner,
+            None => continue,
+        };
+
+        let mut manager = Manager::with_variant(Variant::Standard);
+
+        for (ply, text) in game.moves.iter().enumerate() {
+            let result = match winner {
+                Piece::Empty => 0.5,
+                winner if winner.match_piece(&manager.current_side()) => 1f64,
+                _ => 0f64,
+            };
+
+            if ply >= skip_plies {
+                positions.extend(TuningPosition::new(&manager, result));
+            }
+
+            match pdn::parse_move(&manager, text) {
+                Some(played) => manager.play_move(played),
+                None => break,
+            }
+        }
+    }
+
+    positions
+}
+
+// positions out of the self-play data used to train the network
+pub fn positions_from_samples(samples: &[Sample]) -> Vec<TuningPosition> {
+    samples
+        .iter()
+        .filter_map(|x| {
+            TuningPosition::new(
+                &nn::to_manager(&x.position),
+                (x.result as f64 + 1f64) / 2f64,
+            )
+        })
+        .collect()
+}
+
+fn sigmoid(score: i32, scale: f64) -> f64 {
+    1f64 / (1f64 + 10f64.powf(-scale * score as f64 / 400f64))
+}
+
+// mean squared error of the predicted results
+pub fn error(positions: &[TuningPosition], weights: &Weights, scale: f64) -> f64 {
+    positions
+        .iter()
+        .map(|x| (x.result - sigmoid(x.evaluate(weights), scale)).powi(2))
+        .sum::<f64>()
+        / positions.len().max(1) as f64
+}
+
+// the scale that fits the current weights best, so tuning doesnt just stretch them all
+pub fn fit_scale(positions: &[TuningPosition], weights: &Weights) -> f64 {
+    let mut scale = 1f64;
+    let mut step = 0.5;
+    let mut best = error(positions, weights, scale);
+
+    while step > 0.001 {
+        let mut improved = false;
+
+        for candidate in [scale - step, scale + step] {
+            let candidate_error = error(positions, weights, candidate);
+
+            if candidate > 0f64 && candidate_error < best {
+                (scale, best) = (candidate, candidate_error);
+                improved = true;
+            }
+        }
+
+        if !improved {
+            step /= 2f64;
+        }
+    }
+
+    scale
+}
+
+// moves each weight up or down by one for as long as that lowers the error. the man is
+// kept where it is so the scores stay in the same units
+pub fn tune(
+    positions: &[TuningPosition],
+    mut weights: Weights,
+    scale: f64,
+    max_passes: usize,
+    mut progress: impl FnMut(usize, f64, &Weights),
+) -> Weights {
+    let mut best = error(positions, &weights, scale);
+
+    for pass in 0..max_passes {
+        let mut improved = false;
+
+        for term in 1..TERMS {
+            for step in [1, -1] {
+                let mut candidate = weights;
+                candidate.0[term] += step;
+
+                let candidate_error = error(positions, &candidate, scale);
+
+                if candidate_error < best {
+                    (weights, best) = (candidate, candidate_error);
+                    improved = true;
+                    break;
+                }
+            }
+        }
+
+        progress(pass + 1, best, &weights);
+
+        if !improved {
+            break;
+        }
+    }
+
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error, positions_from_games, tune};
+    use crate::ai::Weights;
+    use crate::pdn::read_games;
+
+    #[test]
+    fn check_tune_lowers_error() {
+        let games = read_games(
+            "1. 11-15 23-19 2. 8-11 22-17 3. 9-13 17x10 4. 7x14 1-0\n\
+             1. 9-14 22-17 2. 11-15 17x10 3. 6x15 23-18 0-1\n",
+        );
+        let positions = positions_from_games(&games, 0);
+        assert!(!positions.is_empty());
+
+        let weights = Weights::default();
+        let tuned = tune(&positions, weights, 1f64, 20, |_, _, _| {});
+
+        assert_eq!(tuned.0[0], weights.0[0]);
+        assert!(error(&positions, &tuned, 1f64) <= error(&positions, &weights, 1f64));
+    }
+}